@@ -0,0 +1,207 @@
+// src/aggregate.rs
+//! Local downsampling: payloads are accumulated per fixed window and replaced by one payload
+//! per window carrying min/max/avg/p95 next to the last value of every numeric field.
+
+use crate::collector::instance_of;
+use serde_json::{Map, Value, json};
+
+/// Samples of one field within a window.
+#[derive(Debug, Default)]
+struct Series {
+    samples: Vec<f64>,
+    last: Value,
+}
+
+impl Series {
+    fn push(&mut self, value: &Value) {
+        if let Some(n) = value.as_f64() {
+            self.samples.push(n);
+        }
+        self.last = value.clone();
+    }
+
+    /// Function to write the field's last value and, when numeric, its statistics.
+    fn emit(&self, name: &str, out: &mut Map<String, Value>) {
+        out.insert(name.to_string(), self.last.clone());
+        if self.samples.is_empty() || !self.last.is_number() {
+            return;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort_by(f64::total_cmp);
+        let n = sorted.len();
+        out.insert(format!("{}_min", name), json!(sorted[0]));
+        out.insert(format!("{}_max", name), json!(sorted[n - 1]));
+        out.insert(
+            format!("{}_avg", name),
+            json!(sorted.iter().sum::<f64>() / n as f64),
+        );
+        out.insert(format!("{}_p95", name), json!(percentile(&sorted, 95.0)));
+    }
+}
+
+/// Function to pick the nearest-rank percentile from sorted samples.
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Fields of one record (a disk, an interface...) within a window.
+#[derive(Debug, Default)]
+struct Record {
+    fields: Vec<(String, Series)>,
+}
+
+impl Record {
+    fn push(&mut self, record: &Map<String, Value>) {
+        for (name, value) in record {
+            match self.fields.iter_mut().find(|(n, _)| n == name) {
+                Some((_, series)) => series.push(value),
+                None => {
+                    let mut series = Series::default();
+                    series.push(value);
+                    self.fields.push((name.clone(), series));
+                }
+            }
+        }
+    }
+
+    fn emit(&self) -> Value {
+        let mut out = Map::new();
+        for (name, series) in &self.fields {
+            series.emit(name, &mut out);
+        }
+        Value::Object(out)
+    }
+}
+
+/// What a collector contributed to the window, following the shape of its output.
+#[derive(Debug)]
+enum Collected {
+    /// Arrays of records, one entry per instance in order of first appearance.
+    Records(Vec<(String, Record)>),
+    Object(Record),
+    Scalar(Series),
+}
+
+/// Accumulates payloads and hands back one aggregated payload per window.
+#[derive(Debug)]
+pub struct Aggregator {
+    window: u64,
+    start: Option<u64>,
+    hostname: Value,
+    labels: Option<Value>,
+    errors: Vec<Value>,
+    collected: Vec<(String, Collected)>,
+}
+
+impl Aggregator {
+    pub fn new(window: u64) -> Self {
+        Self {
+            window: window.max(1),
+            start: None,
+            hostname: Value::Null,
+            labels: None,
+            errors: Vec::new(),
+            collected: Vec::new(),
+        }
+    }
+
+    /// Function to add a payload, returning the previous window's aggregate once the payload
+    /// belongs to a later window. Groups publish out of order around window boundaries, so a
+    /// late payload of an already emitted window is folded into the open one instead of
+    /// reopening (and emitting again) the old window.
+    pub fn push(&mut self, payload: Value) -> Option<Value> {
+        let timestamp = payload["timestamp"].as_u64().unwrap_or(0);
+        let start = timestamp - timestamp % self.window;
+        let finished = if self.start.is_some_and(|current| start > current) {
+            self.take()
+        } else {
+            None
+        };
+
+        self.start = Some(self.start.map_or(start, |current| current.max(start)));
+        self.hostname = payload["hostname"].clone();
+        self.labels = payload.get("labels").cloned();
+        if let Some(errors) = payload["errors"].as_array() {
+            self.errors.extend(errors.iter().cloned());
+        }
+        if let Some(metrics) = payload["metrics"].as_object() {
+            for (key, value) in metrics {
+                self.add(key, value);
+            }
+        }
+
+        finished
+    }
+
+    fn add(&mut self, key: &str, value: &Value) {
+        let index = match self.collected.iter().position(|(k, _)| k == key) {
+            Some(index) => index,
+            None => {
+                let collected = match value {
+                    Value::Array(_) => Collected::Records(Vec::new()),
+                    Value::Object(_) => Collected::Object(Record::default()),
+                    _ => Collected::Scalar(Series::default()),
+                };
+                self.collected.push((key.to_string(), collected));
+                self.collected.len() - 1
+            }
+        };
+
+        match (&mut self.collected[index].1, value) {
+            (Collected::Records(records), Value::Array(items)) => {
+                for item in items.iter().filter_map(Value::as_object) {
+                    let instance = instance_of(item);
+                    match records.iter_mut().find(|(i, _)| *i == instance) {
+                        Some((_, record)) => record.push(item),
+                        None => {
+                            let mut record = Record::default();
+                            record.push(item);
+                            records.push((instance, record));
+                        }
+                    }
+                }
+            }
+            (Collected::Object(record), Value::Object(fields)) => record.push(fields),
+            (Collected::Scalar(series), value) if !value.is_array() && !value.is_object() => {
+                series.push(value)
+            }
+            // A collector that changed shape mid-window keeps its first shape.
+            _ => {}
+        }
+    }
+
+    /// Function to build the aggregate of everything pushed so far and start a new window.
+    /// Returns `None` when nothing was pushed.
+    pub fn take(&mut self) -> Option<Value> {
+        let start = self.start.take()?;
+        let mut metrics = Map::new();
+        for (key, collected) in self.collected.drain(..) {
+            match collected {
+                Collected::Records(records) => {
+                    let records: Vec<Value> = records.iter().map(|(_, r)| r.emit()).collect();
+                    metrics.insert(key, Value::Array(records));
+                }
+                Collected::Object(record) => {
+                    metrics.insert(key, record.emit());
+                }
+                Collected::Scalar(series) => series.emit(&key, &mut metrics),
+            }
+        }
+
+        let mut payload = json!({
+            "timestamp": start,
+            "window": self.window,
+            "hostname": self.hostname.take(),
+            "metrics": metrics,
+            "errors": std::mem::take(&mut self.errors),
+        });
+        if let Some(labels) = self.labels.take() {
+            payload["labels"] = labels;
+        }
+        Some(payload)
+    }
+}
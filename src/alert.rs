@@ -0,0 +1,278 @@
+// src/alert.rs
+//! Threshold alerts evaluated against every published cycle. An alert fires once a rule has held
+//! for its number of cycles and resolves when it stops holding; only these transitions are emitted.
+
+use crate::collector::instance_of;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Comparison used by a rule.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            ">" => Op::Gt,
+            ">=" => Op::Ge,
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            "==" => Op::Eq,
+            "!=" => Op::Ne,
+            _ => return None,
+        })
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Eq => "==",
+            Op::Ne => "!=",
+        }
+    }
+}
+
+/// Value a field is compared against.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Threshold {
+    Number(f64),
+    Text(String),
+}
+
+/// A rule such as `disk_usage.used_percent > 90 for 3`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    /// Payload key of the collector the rule looks at (e.g. `disk_usage`).
+    pub collector: String,
+    pub field: String,
+    pub op: Op,
+    pub threshold: Threshold,
+    /// Consecutive cycles the condition must hold before the alert fires.
+    pub cycles: u32,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{} {} ", self.collector, self.field, self.op.as_str())?;
+        match &self.threshold {
+            Threshold::Number(n) => write!(f, "{}", n)?,
+            Threshold::Text(s) => write!(f, "{}", s)?,
+        }
+        if self.cycles > 1 {
+            write!(f, " for {}", self.cycles)?;
+        }
+        Ok(())
+    }
+}
+
+impl Rule {
+    /// Function to check a record's field against the rule. Missing fields never match.
+    pub fn matches(&self, value: &Value) -> bool {
+        match &self.threshold {
+            Threshold::Number(threshold) => {
+                let Some(v) = value
+                    .as_f64()
+                    .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+                else {
+                    return false;
+                };
+                match self.op {
+                    Op::Gt => v > *threshold,
+                    Op::Ge => v >= *threshold,
+                    Op::Lt => v < *threshold,
+                    Op::Le => v <= *threshold,
+                    Op::Eq => v == *threshold,
+                    Op::Ne => v != *threshold,
+                }
+            }
+            Threshold::Text(threshold) => {
+                let Some(v) = value.as_str() else {
+                    return false;
+                };
+                match self.op {
+                    Op::Eq => v == threshold,
+                    _ => v != threshold,
+                }
+            }
+        }
+    }
+}
+
+/// Function to parse a rule: `<collector>.<field> <op> <value> [for <n> [cycles]]`,
+/// e.g. `smart_log.critical_warning != 0` or `services.status != active`.
+pub fn parse_rule(s: &str) -> Result<Rule, String> {
+    let tokens: Vec<&str> = s.split_whitespace().collect();
+    let (target, op, value, rest) = match tokens.as_slice() {
+        [target, op, value, rest @ ..] => (target, op, value, rest),
+        _ => {
+            return Err(format!(
+                "expected <collector>.<field> <op> <value>, got `{}`",
+                s
+            ));
+        }
+    };
+
+    let (collector, field) = target
+        .split_once('.')
+        .ok_or_else(|| format!("expected <collector>.<field>, got `{}`", target))?;
+    let op = Op::parse(op).ok_or_else(|| format!("unknown comparison `{}`", op))?;
+    let value = value.trim_matches(|c| c == '"' || c == '\'');
+    let threshold = match value.parse::<f64>() {
+        Ok(n) => Threshold::Number(n),
+        Err(_) if matches!(op, Op::Eq | Op::Ne) => Threshold::Text(value.to_string()),
+        Err(_) => return Err(format!("`{}` needs a number, got `{}`", op.as_str(), value)),
+    };
+
+    let cycles = match rest {
+        [] => 1,
+        ["for", n] | ["for", n, "cycles" | "cycle"] => n
+            .parse::<u32>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| format!("invalid number of cycles `{}`", n))?,
+        _ => return Err(format!("unexpected `{}` after the rule", rest.join(" "))),
+    };
+
+    Ok(Rule {
+        collector: collector.replace('-', "_"),
+        field: field.to_string(),
+        op,
+        threshold,
+        cycles,
+    })
+}
+
+/// Per rule and instance progress.
+#[derive(Debug, Default)]
+struct AlertState {
+    breaches: u32,
+    firing_since: Option<u64>,
+}
+
+/// Keeps the state of every rule per instance between cycles.
+#[derive(Debug, Default)]
+pub struct AlertEngine {
+    rules: Vec<Rule>,
+    /// Keyed by rule text and instance, so state survives reloads that keep the rule.
+    states: HashMap<(String, String), AlertState>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self {
+            rules,
+            states: HashMap::new(),
+        }
+    }
+
+    /// Function to replace the rules, keeping the state of rules that did not change.
+    pub fn set_rules(&mut self, rules: Vec<Rule>) {
+        let kept: Vec<String> = rules.iter().map(Rule::to_string).collect();
+        self.states.retain(|(rule, _), _| kept.contains(rule));
+        self.rules = rules;
+    }
+
+    /// Function to evaluate a payload and build an alert payload from the transitions it caused.
+    /// Instances that disappear from a collector's records resolve their alerts.
+    pub fn evaluate(&mut self, payload: &Value) -> Option<Value> {
+        let timestamp = payload["timestamp"].as_u64().unwrap_or(0);
+        let metrics = payload["metrics"].as_object()?;
+        let mut events = Vec::new();
+
+        for rule in &self.rules {
+            // Groups publish different collectors; rules only see the cycles of theirs.
+            let Some(data) = metrics.get(&rule.collector) else {
+                continue;
+            };
+            let records: Vec<&Map<String, Value>> = match data {
+                Value::Array(records) => records.iter().filter_map(Value::as_object).collect(),
+                Value::Object(record) => vec![record],
+                _ => Vec::new(),
+            };
+            let name = rule.to_string();
+
+            let mut seen = Vec::new();
+            for record in records {
+                let instance = instance_of(record);
+                let value = record.get(&rule.field).unwrap_or(&Value::Null);
+                let state = self
+                    .states
+                    .entry((name.clone(), instance.clone()))
+                    .or_default();
+
+                if rule.matches(value) {
+                    state.breaches = state.breaches.saturating_add(1);
+                    if state.breaches >= rule.cycles && state.firing_since.is_none() {
+                        state.firing_since = Some(timestamp);
+                        events.push(event(&name, rule, &instance, "firing", value, timestamp));
+                    }
+                } else {
+                    state.breaches = 0;
+                    if let Some(since) = state.firing_since.take() {
+                        events.push(event(&name, rule, &instance, "resolved", value, since));
+                    }
+                }
+                seen.push(instance);
+            }
+
+            let gone: Vec<String> = self
+                .states
+                .keys()
+                .filter(|(rule, instance)| *rule == name && !seen.contains(instance))
+                .map(|(_, instance)| instance.clone())
+                .collect();
+            for instance in gone {
+                if let Some(state) = self.states.remove(&(name.clone(), instance.clone()))
+                    && let Some(since) = state.firing_since
+                {
+                    events.push(event(
+                        &name,
+                        rule,
+                        &instance,
+                        "resolved",
+                        &Value::Null,
+                        since,
+                    ));
+                }
+            }
+        }
+
+        if events.is_empty() {
+            return None;
+        }
+        let mut alerts = json!({
+            "timestamp": timestamp,
+            "hostname": payload["hostname"],
+            "metrics": { "alerts": events },
+            "errors": [],
+        });
+        if let Some(labels) = payload.get("labels") {
+            alerts["labels"] = labels.clone();
+        }
+        Some(alerts)
+    }
+}
+
+/// Function to build one alert event record.
+fn event(name: &str, rule: &Rule, instance: &str, state: &str, value: &Value, since: u64) -> Value {
+    json!({
+        "rule": name,
+        "state": state,
+        "source": rule.collector,
+        "instance": instance,
+        "field": rule.field,
+        "value": value,
+        "since": since,
+    })
+}
@@ -0,0 +1,261 @@
+// src/check.rs
+//! Nagios/Icinga-compatible checks built on the collectors: a status line with perfdata
+//! and the plugin exit code (0 OK, 1 WARNING, 2 CRITICAL, 3 UNKNOWN).
+
+use crate::collector::{CollectorError, NvmesSmartLog};
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Plugin states, ordered from best to worst so results can be combined with `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    Ok,
+    Warning,
+    Unknown,
+    Critical,
+}
+
+impl Status {
+    /// Function to get the exit code the monitoring system expects for a state.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Status::Ok => 0,
+            Status::Warning => 1,
+            Status::Critical => 2,
+            Status::Unknown => 3,
+        }
+    }
+
+    /// Function to get the state name used in the status line.
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Ok => "OK",
+            Status::Warning => "WARNING",
+            Status::Critical => "CRITICAL",
+            Status::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+/// One performance data value, `'label'=value[uom];[warn];[crit];[min];[max]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Perfdata {
+    pub label: String,
+    pub value: f64,
+    pub uom: &'static str,
+    pub warn: Option<f64>,
+    pub crit: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl Perfdata {
+    fn new(label: impl Into<String>, value: f64, uom: &'static str) -> Self {
+        Self {
+            label: label.into(),
+            value,
+            uom,
+            warn: None,
+            crit: None,
+            min: None,
+            max: None,
+        }
+    }
+}
+
+/// Outcome of a check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: Status,
+    pub summary: String,
+    pub perfdata: Vec<Perfdata>,
+}
+
+impl CheckResult {
+    /// Function to format the plugin output line, e.g. `DISK OK - ... | '/'=41.2%;80;90;0;100`.
+    pub fn line(&self) -> String {
+        let mut line = format!("{} {} - {}", self.name, self.status.label(), self.summary);
+        if !self.perfdata.is_empty() {
+            line.push_str(" |");
+        }
+        for perf in &self.perfdata {
+            let opt = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+            let field = format!(
+                "'{}'={}{};{};{};{};{}",
+                perf.label.replace('\'', "''"),
+                perf.value,
+                perf.uom,
+                opt(perf.warn),
+                opt(perf.crit),
+                opt(perf.min),
+                opt(perf.max)
+            );
+            // Trailing empty fields may be left out.
+            let _ = write!(line, " {}", field.trim_end_matches(';'));
+        }
+        line
+    }
+}
+
+/// Function to check used space of the disks returned by `get_disk_usage`.
+/// Only the given mount points are checked, or every disk when `mounts` is empty.
+pub fn check_disk(disks: &[Value], mounts: &[String], warn: f64, crit: f64) -> CheckResult {
+    let mut status = Status::Ok;
+    let mut problems = Vec::new();
+    let mut perfdata = Vec::new();
+
+    for disk in disks {
+        let mount = disk["mount"].as_str().unwrap_or_default();
+        if !mounts.is_empty() && !mounts.iter().any(|m| m == mount) {
+            continue;
+        }
+        let used = disk["used_percent"].as_f64().unwrap_or(0.0);
+        let disk_status = if used >= crit {
+            Status::Critical
+        } else if used >= warn {
+            Status::Warning
+        } else {
+            Status::Ok
+        };
+        if disk_status != Status::Ok {
+            problems.push(format!("{} {:.1}% used", mount, used));
+        }
+        status = status.max(disk_status);
+        perfdata.push(Perfdata {
+            warn: Some(warn),
+            crit: Some(crit),
+            min: Some(0.0),
+            max: Some(100.0),
+            ..Perfdata::new(mount, (used * 10.0).round() / 10.0, "%")
+        });
+    }
+
+    let missing: Vec<&str> = mounts
+        .iter()
+        .filter(|m| {
+            !disks
+                .iter()
+                .any(|d| d["mount"].as_str() == Some(m.as_str()))
+        })
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        status = status.max(Status::Unknown);
+        problems.push(format!("not mounted: {}", missing.join(", ")));
+    }
+
+    let summary = if perfdata.is_empty() && missing.is_empty() {
+        status = Status::Unknown;
+        "no filesystems found".to_string()
+    } else if problems.is_empty() {
+        format!("{} filesystem(s) below {}% used", perfdata.len(), warn)
+    } else {
+        problems.join(", ")
+    };
+
+    CheckResult {
+        name: "DISK",
+        status,
+        summary,
+        perfdata,
+    }
+}
+
+/// Function to check NVMe health from `collect_smart_log_with_errors`.
+/// A set critical warning is critical, media errors a warning, unreadable controllers unknown.
+pub fn check_smart(logs: &[NvmesSmartLog], errors: &[CollectorError]) -> CheckResult {
+    let mut status = Status::Ok;
+    let mut problems = Vec::new();
+    let mut perfdata = Vec::new();
+
+    for log in logs {
+        let name = &log.nvme_name;
+        let critical_warning = log.critical_warning.unwrap_or(0);
+        let media_errors = log.media_errors.unwrap_or(0);
+        if critical_warning != 0 {
+            status = status.max(Status::Critical);
+            problems.push(format!(
+                "{} critical warning {:#04x}",
+                name, critical_warning
+            ));
+        } else if media_errors > 0 {
+            status = status.max(Status::Warning);
+            problems.push(format!("{} {} media error(s)", name, media_errors));
+        }
+
+        if let Some(kelvin) = log.temperature {
+            // Same conversion as `--transform "convert smart_log.temperature kelvin celsius"`,
+            // rounded to hundredths to keep float noise out of the perfdata.
+            let celsius = crate::transform::convert_unit(kelvin as f64, "kelvin", "celsius")
+                .unwrap_or_default();
+            perfdata.push(Perfdata::new(
+                format!("{}_temperature", name),
+                (celsius * 100.0).round() / 100.0,
+                "",
+            ));
+        }
+        if let Some(used) = log.percent_used {
+            perfdata.push(Perfdata {
+                min: Some(0.0),
+                ..Perfdata::new(format!("{}_percent_used", name), used as f64, "%")
+            });
+        }
+        perfdata.push(Perfdata::new(
+            format!("{}_media_errors", name),
+            media_errors as f64,
+            "c",
+        ));
+    }
+
+    for error in errors {
+        status = status.max(Status::Unknown);
+        problems.push(format!(
+            "{}: {}",
+            error.instance.as_deref().unwrap_or(&error.collector),
+            error.message
+        ));
+    }
+
+    let summary = if logs.is_empty() && errors.is_empty() {
+        status = Status::Unknown;
+        "no NVMe controllers found".to_string()
+    } else if problems.is_empty() {
+        format!("{} NVMe controller(s) healthy", logs.len())
+    } else {
+        problems.join(", ")
+    };
+
+    CheckResult {
+        name: "SMART",
+        status,
+        summary,
+        perfdata,
+    }
+}
+
+/// Function to check the unit states returned by `get_service_status`.
+/// Active is OK, units changing state a warning, anything else critical, "unknown" unknown.
+pub fn check_service(services: &[Value]) -> CheckResult {
+    let mut status = Status::Ok;
+    let mut states = Vec::new();
+
+    for service in services {
+        let name = service["service_name"].as_str().unwrap_or_default();
+        let state = service["status"].as_str().unwrap_or("unknown");
+        status = status.max(match state {
+            "active" => Status::Ok,
+            "activating" | "deactivating" | "reloading" | "refreshing" => Status::Warning,
+            "unknown" => Status::Unknown,
+            _ => Status::Critical,
+        });
+        states.push(format!("{} is {}", name, state));
+    }
+
+    CheckResult {
+        name: "SERVICE",
+        status,
+        summary: states.join(", "),
+        perfdata: Vec::new(),
+    }
+}
@@ -0,0 +1,34 @@
+// src/collector/agent.rs
+//! Self-monitoring: what the tinycollectd process itself is doing.
+
+use crate::stats::STATS;
+use serde_json::{Map, Value, json};
+use sysinfo::System;
+
+/// Function to extract statistics about the running tinycollectd process.
+pub fn get_agent_stats(sys: &mut System) -> Value {
+    let (rss_bytes, cpu_percent) = match sysinfo::get_current_pid() {
+        Ok(pid) if sys.refresh_process(pid) => sys
+            .process(pid)
+            .map_or((0, 0.0), |p| (p.memory(), p.cpu_usage())),
+        _ => (0, 0.0),
+    };
+
+    let durations: Map<String, Value> = STATS
+        .durations()
+        .into_iter()
+        .map(|(collector, d)| (collector, json!(d.as_secs_f64() * 1000.0)))
+        .collect();
+
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "pid": std::process::id(),
+        "rss_bytes": rss_bytes,
+        "cpu_percent": cpu_percent,
+        "bytes_sent": STATS.bytes_sent(),
+        "datagrams_sent": STATS.datagrams_sent(),
+        "send_failures": STATS.send_failures(),
+        "dropped_samples": STATS.dropped_samples(),
+        "collection_duration_ms": durations,
+    })
+}
@@ -0,0 +1,162 @@
+// src/collector/cpu.rs
+//! CPU utilization from /proc/stat: time spent per state as a percentage of the interval since
+//! the previous collection, for the whole machine and for every core, plus scheduler counters.
+
+use crate::collector::error::CollectorError;
+use serde_json::{Map, Value, json};
+use std::time::Instant;
+
+/// Columns of a `cpu` line, in /proc/stat order.
+pub const CPU_STATES: [&str; 10] = [
+    "user",
+    "nice",
+    "system",
+    "idle",
+    "iowait",
+    "irq",
+    "softirq",
+    "steal",
+    "guest",
+    "guest_nice",
+];
+
+/// Jiffies one CPU (or all of them, named `all`) spent in each state since boot.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CpuTimes {
+    pub cpu: String,
+    pub times: [u64; 10],
+}
+
+impl CpuTimes {
+    /// Function to sum the time spent in every state. Guest time is already part of user
+    /// and nice time, so it is not counted twice.
+    fn total(&self) -> u64 {
+        self.times[..8].iter().sum()
+    }
+}
+
+/// The parts of /proc/stat the collector reports.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcStat {
+    pub cpus: Vec<CpuTimes>,
+    pub ctxt: u64,
+    pub processes: u64,
+    pub procs_running: u64,
+    pub procs_blocked: u64,
+}
+
+/// Function to parse the contents of /proc/stat.
+pub fn parse_proc_stat(text: &str) -> ProcStat {
+    let mut stat = ProcStat::default();
+    for line in text.lines() {
+        let mut fields = line.split_whitespace();
+        let Some(name) = fields.next() else {
+            continue;
+        };
+        let mut value = || {
+            fields
+                .next()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(0)
+        };
+        match name {
+            "ctxt" => stat.ctxt = value(),
+            "processes" => stat.processes = value(),
+            "procs_running" => stat.procs_running = value(),
+            "procs_blocked" => stat.procs_blocked = value(),
+            cpu if cpu.starts_with("cpu") => {
+                let mut times = [0; 10];
+                // Older kernels report fewer columns; the missing ones stay zero.
+                for time in times.iter_mut() {
+                    *time = value();
+                }
+                stat.cpus.push(CpuTimes {
+                    cpu: if cpu == "cpu" { "all" } else { cpu }.to_string(),
+                    times,
+                });
+            }
+            _ => {}
+        }
+    }
+    stat
+}
+
+/// Function to turn two samples taken `seconds` apart into one record per CPU, `all` first.
+/// Like `--rates`, the first sample only records a baseline: without a previous sample (or for
+/// a CPU that just came online) there are no percentages, and only the `all` record with its
+/// counters is reported.
+pub fn cpu_usage(previous: Option<(&ProcStat, f64)>, current: &ProcStat) -> Vec<Value> {
+    let mut records = Vec::new();
+    for now in &current.cpus {
+        let before = previous.and_then(|(stat, _)| stat.cpus.iter().find(|c| c.cpu == now.cpu));
+        if before.is_none() && now.cpu != "all" {
+            continue;
+        }
+
+        let mut record = Map::new();
+        record.insert("cpu".to_string(), json!(now.cpu));
+        if let Some(before) = before {
+            let total = now.total().saturating_sub(before.total());
+            for (i, state) in CPU_STATES.iter().enumerate() {
+                let delta = now.times[i].saturating_sub(before.times[i]);
+                let percent = if total == 0 {
+                    0.0
+                } else {
+                    delta as f64 * 100.0 / total as f64
+                };
+                record.insert(format!("{}_percent", state), json!(percent));
+            }
+        }
+
+        if now.cpu == "all" {
+            record.insert("context_switches".to_string(), json!(current.ctxt));
+            record.insert("forks".to_string(), json!(current.processes));
+            record.insert("procs_running".to_string(), json!(current.procs_running));
+            record.insert("procs_blocked".to_string(), json!(current.procs_blocked));
+            if let Some((before, seconds)) = previous.filter(|(_, s)| *s > 0.0) {
+                let rate = |now: u64, then: u64| now.saturating_sub(then) as f64 / seconds;
+                record.insert(
+                    "context_switches_per_sec".to_string(),
+                    json!(rate(current.ctxt, before.ctxt)),
+                );
+                record.insert(
+                    "forks_per_sec".to_string(),
+                    json!(rate(current.processes, before.processes)),
+                );
+            }
+        }
+        records.push(Value::Object(record));
+    }
+    records
+}
+
+/// The sample a cpu collector took last, kept by the scheduler between its cycles.
+#[derive(Debug, Default)]
+pub struct CpuState {
+    previous: Option<(Instant, ProcStat)>,
+}
+
+/// Function to read /proc/stat and report utilization since the previous call with `state`.
+pub fn get_cpu_usage_with_errors(state: &mut CpuState) -> (Vec<Value>, Vec<CollectorError>) {
+    let text = match std::fs::read_to_string("/proc/stat") {
+        Ok(text) => text,
+        Err(e) => {
+            return (
+                Vec::new(),
+                vec![CollectorError::from_io("cpu", "/proc/stat", &e)],
+            );
+        }
+    };
+    let current = parse_proc_stat(&text);
+    let now = Instant::now();
+
+    let records = cpu_usage(
+        state
+            .previous
+            .as_ref()
+            .map(|(then, stat)| (stat, now.duration_since(*then).as_secs_f64())),
+        &current,
+    );
+    state.previous = Some((now, current));
+    (records, Vec::new())
+}
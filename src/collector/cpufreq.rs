@@ -0,0 +1,109 @@
+// src/collector/cpufreq.rs
+//! Per-core frequency scaling state from /sys/devices/system/cpu/cpu*/cpufreq.
+
+use crate::collector::error::CollectorError;
+use serde_json::{Map, Value, json};
+use std::fs;
+use std::io;
+use std::path::Path;
+use sysinfo::System;
+
+/// Where the kernel exposes one directory per CPU.
+pub const CPU_SYSFS: &str = "/sys/devices/system/cpu";
+
+/// Frequency files reported per core, as (file, field). The kernel reports kHz.
+const FREQUENCIES: &[(&str, &str)] = &[
+    ("scaling_cur_freq", "cur_freq_mhz"),
+    ("scaling_min_freq", "min_freq_mhz"),
+    ("scaling_max_freq", "max_freq_mhz"),
+];
+
+/// Text files reported per core, as (file, field).
+const SETTINGS: &[(&str, &str)] = &[
+    ("scaling_governor", "governor"),
+    (
+        "energy_performance_preference",
+        "energy_performance_preference",
+    ),
+];
+
+/// Function to read one cpufreq file. Files a driver does not provide are `None`.
+fn read_value(dir: &Path, file: &str) -> io::Result<Option<String>> {
+    match fs::read_to_string(dir.join(file)) {
+        Ok(value) => Ok(Some(value.trim().to_string())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Function to read the cpufreq state of every core under `root`, ordered by CPU number.
+/// Cores without a cpufreq directory (no scaling driver, offline) are skipped.
+pub fn read_cpufreq(root: &Path) -> (Vec<Value>, Vec<CollectorError>) {
+    let mut cpus: Vec<(u32, String)> = match fs::read_dir(root) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let number = name.strip_prefix("cpu")?.parse().ok()?;
+                Some((number, name))
+            })
+            .collect(),
+        Err(e) => {
+            let root = root.to_string_lossy();
+            return (
+                Vec::new(),
+                vec![CollectorError::from_io("cpufreq", &root, &e)],
+            );
+        }
+    };
+    cpus.sort();
+
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    for (_, cpu) in cpus {
+        let dir = root.join(&cpu).join("cpufreq");
+        if !dir.is_dir() {
+            continue;
+        }
+
+        let mut record = Map::new();
+        record.insert("cpu".to_string(), json!(cpu));
+        for (file, field) in FREQUENCIES {
+            match read_value(&dir, file) {
+                Ok(Some(khz)) => {
+                    if let Ok(khz) = khz.parse::<u64>() {
+                        record.insert(field.to_string(), json!(khz as f64 / 1000.0));
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => errors.push(CollectorError::from_io("cpufreq", &cpu, &e)),
+            }
+        }
+        for (file, field) in SETTINGS {
+            match read_value(&dir, file) {
+                Ok(Some(value)) => {
+                    record.insert(field.to_string(), json!(value));
+                }
+                Ok(None) => {}
+                Err(e) => errors.push(CollectorError::from_io("cpufreq", &cpu, &e)),
+            }
+        }
+        records.push(Value::Object(record));
+    }
+    (records, errors)
+}
+
+/// Function to collect per-core frequencies, falling back to the frequencies sysinfo reports
+/// when the kernel exposes no cpufreq state (common in virtual machines).
+pub fn get_cpufreq_with_errors(sys: &System) -> (Vec<Value>, Vec<CollectorError>) {
+    let (records, errors) = read_cpufreq(Path::new(CPU_SYSFS));
+    if !records.is_empty() {
+        return (records, errors);
+    }
+    let records = sys
+        .cpus()
+        .iter()
+        .map(|cpu| json!({ "cpu": cpu.name(), "cur_freq_mhz": cpu.frequency() as f64 }))
+        .collect();
+    (records, errors)
+}
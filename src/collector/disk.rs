@@ -1,15 +1,26 @@
 // src/collector/disk.rs
 //! Disk usage metrics.
 
+use crate::collector::filter::Filter;
 use serde_json::{Value, json};
 use sysinfo::Disks;
 
 /// Function to extract disk usage data.
 pub fn get_disk_usage() -> Vec<Value> {
+    get_disk_usage_filtered(&Filter::default(), &Filter::default())
+}
+
+/// Function to extract disk usage data for the disks whose mount point and file system type
+/// pass the filters.
+pub fn get_disk_usage_filtered(mounts: &Filter, fstypes: &Filter) -> Vec<Value> {
     let disks = Disks::new_with_refreshed_list();
 
     disks
         .iter()
+        .filter(|disk| {
+            mounts.allows(&disk.mount_point().to_string_lossy())
+                && fstypes.allows(&disk.file_system().to_string_lossy())
+        })
         .map(|disk| {
             let total = disk.total_space();
             let available = disk.available_space();
@@ -0,0 +1,58 @@
+// src/collector/error.rs
+//! Structured collector errors reported in the payload's `errors` section.
+
+use serde::Serialize;
+use std::fmt::Display;
+use std::io;
+
+/// Broad category of a collection failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    PermissionDenied,
+    NotFound,
+    Timeout,
+    Io,
+    Command,
+    Serialization,
+    Panic,
+}
+
+impl From<&io::Error> for ErrorKind {
+    fn from(e: &io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            io::ErrorKind::NotFound => ErrorKind::NotFound,
+            io::ErrorKind::TimedOut => ErrorKind::Timeout,
+            _ => ErrorKind::Io,
+        }
+    }
+}
+
+/// A failure of one collector, optionally narrowed to one instance (device, service...).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CollectorError {
+    pub collector: String,
+    pub instance: Option<String>,
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl CollectorError {
+    pub fn new(collector: &str, kind: ErrorKind, message: impl Display) -> Self {
+        Self {
+            collector: collector.to_string(),
+            instance: None,
+            kind,
+            message: message.to_string(),
+        }
+    }
+
+    /// Function to build an error for a specific instance from an io error.
+    pub fn from_io(collector: &str, instance: &str, e: &io::Error) -> Self {
+        Self {
+            instance: Some(instance.to_string()),
+            ..Self::new(collector, e.into(), e)
+        }
+    }
+}
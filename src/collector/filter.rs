@@ -0,0 +1,69 @@
+// src/collector/filter.rs
+//! Include/exclude filters that decide which disks, interfaces and controllers are collected.
+
+use regex::Regex;
+
+/// A name pattern: a glob (`veth*`, `/run/?`) or, when prefixed with `~`, a regular expression.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    regex: Regex,
+}
+
+impl Pattern {
+    /// Function to check whether a whole name matches the pattern.
+    pub fn matches(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+/// Function to parse a filter pattern. Globs support `*` and `?` and must match the whole name;
+/// regular expressions (`~^veth[0-9a-f]+$`) match anywhere unless anchored.
+pub fn parse_pattern(s: &str) -> Result<Pattern, String> {
+    let expr = match s.strip_prefix('~') {
+        Some(expr) => expr.to_string(),
+        None => {
+            let mut expr = String::from("^");
+            for c in s.chars() {
+                match c {
+                    '*' => expr.push_str(".*"),
+                    '?' => expr.push('.'),
+                    c => expr.push_str(&regex::escape(&c.to_string())),
+                }
+            }
+            expr.push('$');
+            expr
+        }
+    };
+    let regex = Regex::new(&expr).map_err(|e| format!("invalid pattern `{}`: {}", s, e))?;
+    Ok(Pattern {
+        source: s.to_string(),
+        regex,
+    })
+}
+
+/// Names to keep: everything matching an include pattern (or everything, without any)
+/// that matches no exclude pattern.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter {
+    pub include: Vec<Pattern>,
+    pub exclude: Vec<Pattern>,
+}
+
+impl Filter {
+    pub fn new(include: Vec<Pattern>, exclude: Vec<Pattern>) -> Self {
+        Self { include, exclude }
+    }
+
+    /// Function to check whether a name passes the filter.
+    pub fn allows(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| p.matches(name)))
+            && !self.exclude.iter().any(|p| p.matches(name))
+    }
+}
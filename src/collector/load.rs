@@ -0,0 +1,84 @@
+// src/collector/load.rs
+//! Load averages and task counts from /proc/loadavg.
+
+use crate::collector::cpu::parse_proc_stat;
+use crate::collector::error::{CollectorError, ErrorKind};
+use serde_json::{Value, json};
+
+/// Function to parse /proc/loadavg (`0.26 0.24 0.26 2/71 8715`), adding the averages divided
+/// by the number of CPUs so hosts of different sizes compare.
+pub fn parse_loadavg(text: &str, cpus: usize) -> Result<Value, String> {
+    let fields: Vec<&str> = text.split_whitespace().collect();
+    let [load1, load5, load15, tasks, last_pid] = fields.as_slice() else {
+        return Err(format!(
+            "unexpected /proc/loadavg contents `{}`",
+            text.trim()
+        ));
+    };
+    let average = |s: &str| {
+        s.parse::<f64>()
+            .map_err(|_| format!("invalid load average `{}`", s))
+    };
+    let count = |s: &str| {
+        s.parse::<u64>()
+            .map_err(|_| format!("invalid count `{}`", s))
+    };
+    let (runnable, total) = tasks
+        .split_once('/')
+        .ok_or_else(|| format!("invalid task counts `{}`", tasks))?;
+
+    let (load1, load5, load15) = (average(load1)?, average(load5)?, average(load15)?);
+    let cpus = cpus.max(1) as f64;
+    Ok(json!({
+        "load1": load1,
+        "load5": load5,
+        "load15": load15,
+        "load1_per_cpu": load1 / cpus,
+        "load5_per_cpu": load5 / cpus,
+        "load15_per_cpu": load15 / cpus,
+        "runnable_tasks": count(runnable)?,
+        "total_tasks": count(total)?,
+        "last_pid": count(last_pid)?,
+    }))
+}
+
+/// Function to count the online CPUs of the whole system from the `cpuN` lines of /proc/stat.
+/// Unlike `available_parallelism`, this ignores the process's CPU quota and affinity, which do
+/// not limit the system-wide load averages.
+fn online_cpus() -> usize {
+    let cpus = std::fs::read_to_string("/proc/stat")
+        .map(|text| {
+            parse_proc_stat(&text)
+                .cpus
+                .iter()
+                .filter(|cpu| cpu.cpu != "all")
+                .count()
+        })
+        .unwrap_or(0);
+    if cpus > 0 {
+        cpus
+    } else {
+        std::thread::available_parallelism().map_or(1, |n| n.get())
+    }
+}
+
+/// Function to read the load averages along with collection errors.
+pub fn get_load_with_errors() -> (Value, Vec<CollectorError>) {
+    let text = match std::fs::read_to_string("/proc/loadavg") {
+        Ok(text) => text,
+        Err(e) => {
+            return (
+                json!({}),
+                vec![CollectorError::from_io("load", "/proc/loadavg", &e)],
+            );
+        }
+    };
+    let cpus = online_cpus();
+    match parse_loadavg(&text, cpus) {
+        Ok(load) => (load, Vec::new()),
+        Err(e) => (
+            json!({}),
+            vec![CollectorError::new("load", ErrorKind::Io, e)],
+        ),
+    }
+}
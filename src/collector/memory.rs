@@ -0,0 +1,102 @@
+// src/collector/memory.rs
+//! Memory, swap and hugepage usage from /proc/meminfo, in bytes and computed the way `free` does.
+
+use crate::collector::error::{CollectorError, ErrorKind};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+
+/// /proc/meminfo fields reported as-is, as (meminfo key, field). Sizes are converted to bytes.
+const FIELDS: &[(&str, &str)] = &[
+    ("MemTotal", "total_bytes"),
+    ("MemFree", "free_bytes"),
+    ("MemAvailable", "available_bytes"),
+    ("Buffers", "buffers_bytes"),
+    ("Shmem", "shmem_bytes"),
+    ("Slab", "slab_bytes"),
+    ("Dirty", "dirty_bytes"),
+    ("Writeback", "writeback_bytes"),
+    ("SwapTotal", "swap_total_bytes"),
+    ("SwapFree", "swap_free_bytes"),
+    ("SwapCached", "swap_cached_bytes"),
+    ("HugePages_Total", "hugepages_total"),
+    ("HugePages_Free", "hugepages_free"),
+    ("HugePages_Rsvd", "hugepages_reserved"),
+    ("HugePages_Surp", "hugepages_surplus"),
+    ("Hugepagesize", "hugepage_size_bytes"),
+    ("AnonHugePages", "anon_hugepages_bytes"),
+];
+
+/// Function to parse /proc/meminfo into a memory record. Values with a `kB` unit become bytes,
+/// hugepage counts stay counts. Fields the kernel does not report are left out.
+pub fn parse_meminfo(text: &str) -> Result<Value, String> {
+    let mut values = HashMap::new();
+    for line in text.lines() {
+        let Some((key, rest)) = line.split_once(':') else {
+            continue;
+        };
+        let mut parts = rest.split_whitespace();
+        let Some(Ok(value)) = parts.next().map(str::parse::<u64>) else {
+            continue;
+        };
+        let value = match parts.next() {
+            Some("kB") => value * 1024,
+            _ => value,
+        };
+        values.insert(key.trim(), value);
+    }
+    let Some(&total) = values.get("MemTotal") else {
+        return Err("MemTotal missing from /proc/meminfo".to_string());
+    };
+
+    let mut record = Map::new();
+    for (key, field) in FIELDS {
+        if let Some(value) = values.get(key) {
+            record.insert(field.to_string(), json!(value));
+        }
+    }
+
+    // Like `free`: the page cache includes reclaimable slab, and used memory is what is not
+    // available (falling back to free + buffers + cache on kernels without MemAvailable).
+    let get = |key: &str| values.get(key).copied().unwrap_or(0);
+    let cached = get("Cached") + get("SReclaimable");
+    let available = values
+        .get("MemAvailable")
+        .copied()
+        .unwrap_or(get("MemFree") + get("Buffers") + cached);
+    let used = total.saturating_sub(available);
+    record.insert("cached_bytes".to_string(), json!(cached));
+    record.insert("used_bytes".to_string(), json!(used));
+    record.insert(
+        "used_percent".to_string(),
+        json!(if total == 0 {
+            0.0
+        } else {
+            used as f64 * 100.0 / total as f64
+        }),
+    );
+    record.insert(
+        "swap_used_bytes".to_string(),
+        json!(get("SwapTotal").saturating_sub(get("SwapFree"))),
+    );
+    Ok(Value::Object(record))
+}
+
+/// Function to read memory usage along with collection errors.
+pub fn get_memory_with_errors() -> (Value, Vec<CollectorError>) {
+    let text = match std::fs::read_to_string("/proc/meminfo") {
+        Ok(text) => text,
+        Err(e) => {
+            return (
+                json!({}),
+                vec![CollectorError::from_io("memory", "/proc/meminfo", &e)],
+            );
+        }
+    };
+    match parse_meminfo(&text) {
+        Ok(memory) => (memory, Vec::new()),
+        Err(e) => (
+            json!({}),
+            vec![CollectorError::new("memory", ErrorKind::Io, e)],
+        ),
+    }
+}
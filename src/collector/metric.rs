@@ -0,0 +1,217 @@
+// src/collector/metric.rs
+//! Metric types tinycollectd can collect and the dispatch to their collectors.
+
+use crate::collector::cpu::CpuState;
+use crate::collector::error::{CollectorError, ErrorKind};
+use crate::collector::filter::Filter;
+use clap::ValueEnum;
+use serde_json::{Map, Value, json};
+use sysinfo::System;
+
+#[derive(ValueEnum, Clone, Debug, PartialEq)]
+pub enum MetricType {
+    All,
+    DiskUsage,
+    Network,
+    /// Per-core current, minimum and maximum frequency, governor and energy preference, plus the
+    /// first core's frequency as `cpu_freq_mhz`.
+    Cpufreq,
+    Uptime,
+    SmartLog,
+    Services,
+    /// Per-core and total CPU utilization from /proc/stat.
+    Cpu,
+    /// Load averages, overall and per CPU, and task counts from /proc/loadavg.
+    Load,
+    /// Memory, swap and hugepage usage from /proc/meminfo.
+    Memory,
+    /// Pressure stall information, system-wide and per cgroup.
+    Pressure,
+    /// tinycollectd's own statistics.
+    #[value(name = "self")]
+    SelfStats,
+}
+
+/// Fields that tell records of one collector apart (the mount of a disk, the name of an interface...).
+pub const INSTANCE_KEYS: &[&str] = &[
+    "mount",
+    "interface",
+    "nvme_name",
+    "service_name",
+    "cpu",
+    "cgroup",
+];
+
+/// Function to name the instance a record describes, empty for single-record collectors.
+pub fn instance_of(record: &Map<String, Value>) -> String {
+    INSTANCE_KEYS
+        .iter()
+        .find_map(|key| record.get(*key).and_then(Value::as_str))
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Settings collectors need beyond the list of metrics.
+#[derive(Debug, Clone, Default)]
+pub struct CollectorConfig {
+    /// systemd units queried by the services collector.
+    pub services: Vec<String>,
+    /// Mount points reported by the disk collector.
+    pub disk_mounts: Filter,
+    /// File system types reported by the disk collector (e.g. excluding tmpfs).
+    pub disk_fstypes: Filter,
+    /// Interfaces reported by the network collector.
+    pub interfaces: Filter,
+    /// NVMe controllers read by the smart log collector.
+    pub nvme: Filter,
+    /// cgroups whose pressure is reported by the pressure collector.
+    pub cgroups: Filter,
+    /// Levels of the cgroup hierarchy walked by the pressure collector
+    /// [default: `DEFAULT_CGROUP_DEPTH`].
+    pub cgroup_depth: Option<usize>,
+}
+
+/// What collectors keep between their cycles, one per scheduled collector.
+#[derive(Debug, Default)]
+pub struct CollectorState {
+    /// Previous /proc/stat sample of the cpu collector.
+    pub cpu: CpuState,
+}
+
+impl MetricType {
+    /// Function to list the individual collectors `All` stands for.
+    pub fn expand_all() -> Vec<MetricType> {
+        vec![
+            MetricType::DiskUsage,
+            MetricType::Network,
+            MetricType::Cpufreq,
+            MetricType::Uptime,
+            MetricType::SmartLog,
+        ]
+    }
+
+    /// Function to get the key the collector's output is stored under.
+    pub fn key(&self) -> &'static str {
+        match self {
+            MetricType::All => "all",
+            MetricType::DiskUsage => "disk_usage",
+            MetricType::Network => "network",
+            MetricType::Cpufreq => "cpufreq",
+            MetricType::Uptime => "uptime",
+            MetricType::SmartLog => "smart_log",
+            MetricType::Services => "services",
+            MetricType::Cpu => "cpu",
+            MetricType::Load => "load",
+            MetricType::Memory => "memory",
+            MetricType::Pressure => "pressure",
+            MetricType::SelfStats => "self",
+        }
+    }
+
+    /// Function to check whether the collector reads from a refreshed `System`.
+    pub fn needs_system(&self) -> bool {
+        matches!(self, MetricType::All | MetricType::Cpufreq)
+    }
+}
+
+/// Function to run a single collector, returning the entries it adds to the metrics object
+/// and whatever went wrong along the way.
+pub fn collect_metric(
+    metric: &MetricType,
+    sys: &mut System,
+    config: &CollectorConfig,
+    state: &mut CollectorState,
+) -> (Map<String, Value>, Vec<CollectorError>) {
+    if metric.needs_system() {
+        sys.refresh_all();
+    }
+
+    let mut metrics_obj = Map::new();
+    let mut errors = Vec::new();
+    match metric {
+        MetricType::All => {
+            let (info, info_errors) = crate::collector::sys::get_sysinfo_with_errors(sys, config);
+            if let Value::Object(map) = info {
+                metrics_obj.extend(map);
+            }
+            errors.extend(info_errors);
+        }
+        MetricType::SmartLog => {
+            let (smart_log, smart_errors) =
+                crate::collector::nvme::collect_smart_log_filtered(&config.nvme);
+            errors.extend(smart_errors);
+            let smart_val = serde_json::to_value(smart_log).unwrap_or_else(|e| {
+                errors.push(CollectorError::new(
+                    metric.key(),
+                    ErrorKind::Serialization,
+                    e,
+                ));
+                json!([])
+            });
+            metrics_obj.insert(metric.key().to_string(), smart_val);
+        }
+        MetricType::DiskUsage => {
+            let disk_data = crate::collector::disk::get_disk_usage_filtered(
+                &config.disk_mounts,
+                &config.disk_fstypes,
+            );
+            metrics_obj.insert(metric.key().to_string(), Value::Array(disk_data));
+        }
+        MetricType::Network => {
+            let network_data = crate::collector::net::get_if_data_filtered(&config.interfaces);
+            metrics_obj.insert(metric.key().to_string(), Value::Array(network_data));
+        }
+        MetricType::Cpufreq => {
+            let (cpufreq, cpufreq_errors) = crate::collector::cpufreq::get_cpufreq_with_errors(sys);
+            // Keep the first core's `cpu_freq_mhz` this collector used to report on its own.
+            if let Value::Object(legacy) = crate::collector::cpu_freq_json(sys) {
+                metrics_obj.extend(legacy);
+            }
+            metrics_obj.insert(metric.key().to_string(), Value::Array(cpufreq));
+            errors.extend(cpufreq_errors);
+        }
+        MetricType::Uptime => {
+            metrics_obj.insert(metric.key().to_string(), crate::collector::uptime_json());
+        }
+        MetricType::Services => {
+            let (statuses, service_errors) =
+                crate::collector::services::get_service_status_with_errors(&config.services);
+            metrics_obj.insert(metric.key().to_string(), Value::Array(statuses));
+            errors.extend(service_errors);
+        }
+        MetricType::Cpu => {
+            let (usage, cpu_errors) =
+                crate::collector::cpu::get_cpu_usage_with_errors(&mut state.cpu);
+            metrics_obj.insert(metric.key().to_string(), Value::Array(usage));
+            errors.extend(cpu_errors);
+        }
+        MetricType::Load => {
+            let (load, load_errors) = crate::collector::load::get_load_with_errors();
+            metrics_obj.insert(metric.key().to_string(), load);
+            errors.extend(load_errors);
+        }
+        MetricType::Memory => {
+            let (memory, memory_errors) = crate::collector::memory::get_memory_with_errors();
+            metrics_obj.insert(metric.key().to_string(), memory);
+            errors.extend(memory_errors);
+        }
+        MetricType::Pressure => {
+            let (pressure, pressure_errors) = crate::collector::pressure::get_pressure_with_errors(
+                &config.cgroups,
+                config
+                    .cgroup_depth
+                    .unwrap_or(crate::collector::pressure::DEFAULT_CGROUP_DEPTH),
+            );
+            metrics_obj.insert(metric.key().to_string(), Value::Array(pressure));
+            errors.extend(pressure_errors);
+        }
+        MetricType::SelfStats => {
+            metrics_obj.insert(
+                metric.key().to_string(),
+                crate::collector::agent::get_agent_stats(sys),
+            );
+        }
+    }
+
+    (metrics_obj, errors)
+}
@@ -1,16 +1,43 @@
 // src/collector/mod.rs
 
+pub mod agent;
+pub mod cpu;
+pub mod cpufreq;
 pub mod disk;
+pub mod error;
+pub mod filter;
+pub mod load;
+pub mod memory;
+pub mod metric;
 pub mod net;
 pub mod nvme;
+pub mod pressure;
 pub mod services;
 pub mod sys;
 
-pub use sys::{cpu_freq_json, get_hostname, get_sysinfo, get_timestamp, uptime_json};
+pub use sys::{
+    cpu_freq_json, get_hostname, get_sysinfo, get_sysinfo_with_errors, get_timestamp, uptime_json,
+};
 
-pub use disk::get_disk_usage;
-pub use net::get_if_data;
+pub use agent::get_agent_stats;
+pub use cpu::{
+    CpuState, CpuTimes, ProcStat, cpu_usage, get_cpu_usage_with_errors, parse_proc_stat,
+};
+pub use cpufreq::{get_cpufreq_with_errors, read_cpufreq};
+pub use disk::{get_disk_usage, get_disk_usage_filtered};
+pub use error::{CollectorError, ErrorKind};
+pub use filter::{Filter, Pattern, parse_pattern};
+pub use load::{get_load_with_errors, parse_loadavg};
+pub use memory::{get_memory_with_errors, parse_meminfo};
+pub use metric::{
+    CollectorConfig, CollectorState, INSTANCE_KEYS, MetricType, collect_metric, instance_of,
+};
+pub use net::{get_if_data, get_if_data_filtered};
 
-pub use services::get_service_status;
+pub use pressure::{cgroup2_mount, get_pressure_with_errors, parse_pressure, read_cgroup_pressure};
+pub use services::{get_service_status, get_service_status_with_errors};
 
-pub use nvme::{NvmesSmartLog, collect_smart_log, list_nvme_controllers};
+pub use nvme::{
+    NvmesSmartLog, collect_smart_log, collect_smart_log_filtered, collect_smart_log_with_errors,
+    list_nvme_controllers,
+};
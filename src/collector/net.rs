@@ -1,15 +1,22 @@
 // src/collector/net.rs
 //! Network interface metrics.
 
+use crate::collector::filter::Filter;
 use serde_json::{Value, json};
 use sysinfo::Networks;
 
 /// Function to extract interface data.
 pub fn get_if_data() -> Vec<Value> {
+    get_if_data_filtered(&Filter::default())
+}
+
+/// Function to extract interface data for the interfaces whose name passes the filter.
+pub fn get_if_data_filtered(interfaces: &Filter) -> Vec<Value> {
     let networks = Networks::new_with_refreshed_list();
 
     networks
         .iter()
+        .filter(|(name, _)| interfaces.allows(name))
         .map(|(name, data)| {
             json!({
                 "interface": name.replace('"', "\\\""),
@@ -1,6 +1,8 @@
 // src/collector/nvme.rs
 //! NVMe SMART collection via linux_nvme_sys.
 
+use crate::collector::error::{CollectorError, ErrorKind};
+use crate::collector::filter::Filter;
 use nvme_cli_sys::{nvme_admin_cmd, nvme_admin_opcode::nvme_admin_get_log_page, nvme_smart_log};
 use serde::Serialize;
 use std::fs::{self, OpenOptions};
@@ -8,7 +10,7 @@ use std::io;
 use std::mem::{size_of, zeroed};
 use std::os::unix::io::AsRawFd;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
 pub struct NvmesSmartLog {
     /// NVMe device name (e.g., "nvme0")
     /// Potential issue - we use u64 for all values in the struct.
@@ -216,16 +218,19 @@ impl NvmesSmartLog {
 
 /// Function to discover controllers exposed on the server.
 pub fn list_nvme_controllers() -> Vec<String> {
+    read_nvme_controllers().unwrap_or_default()
+}
+
+/// Function to discover controllers, failing if the sysfs class can not be read.
+fn read_nvme_controllers() -> io::Result<Vec<String>> {
     let mut names = Vec::new();
 
-    if let Ok(entries) = fs::read_dir("/sys/class/nvme") {
-        for entry in entries.flatten() {
-            let name = entry.file_name().to_string_lossy().into_owned();
-            names.push(name);
-        }
+    for entry in fs::read_dir("/sys/class/nvme")?.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        names.push(name);
     }
 
-    names
+    Ok(names)
 }
 
 /// Function to extract raw nvme_smart_log from a controller.
@@ -282,10 +287,40 @@ pub fn get_nvme_smart_log_raw(dev_path: &str) -> io::Result<nvme_smart_log> {
 
 /// Function to collect extracted smart log data.
 pub fn collect_smart_log() -> Vec<NvmesSmartLog> {
+    let (results, errors) = collect_smart_log_with_errors();
+    for e in errors {
+        eprintln!(
+            "Failed to fetch SMART log for {}: {}",
+            e.instance.as_deref().unwrap_or("nvme"),
+            e.message
+        );
+    }
+    results
+}
+
+/// Function to collect extracted smart log data along with the devices that could not be read.
+/// A host without NVMe support yields no logs and no errors.
+pub fn collect_smart_log_with_errors() -> (Vec<NvmesSmartLog>, Vec<CollectorError>) {
+    collect_smart_log_filtered(&Filter::default())
+}
+
+/// Function to collect smart log data from the controllers whose name passes the filter.
+pub fn collect_smart_log_filtered(
+    controllers: &Filter,
+) -> (Vec<NvmesSmartLog>, Vec<CollectorError>) {
     let mut results = Vec::new();
-    let ctrls = list_nvme_controllers();
+    let mut errors = Vec::new();
+
+    let ctrls = match read_nvme_controllers() {
+        Ok(ctrls) => ctrls,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => {
+            errors.push(CollectorError::from_io("smart_log", "/sys/class/nvme", &e));
+            Vec::new()
+        }
+    };
 
-    for ctrl in ctrls {
+    for ctrl in ctrls.into_iter().filter(|ctrl| controllers.allows(ctrl)) {
         let dev_path = format!("/dev/{}", ctrl);
 
         match get_nvme_smart_log_raw(&dev_path) {
@@ -293,10 +328,14 @@ pub fn collect_smart_log() -> Vec<NvmesSmartLog> {
                 let mapped = NvmesSmartLog::new(ctrl.clone(), &raw);
                 results.push(mapped);
             }
-            Err(e) => {
-                eprintln!("Failed to fetch SMART log for {}: {}", dev_path, e);
+            Err(e) if e.kind() == io::ErrorKind::Other => {
+                errors.push(CollectorError {
+                    instance: Some(dev_path),
+                    ..CollectorError::new("smart_log", ErrorKind::Command, e)
+                });
             }
+            Err(e) => errors.push(CollectorError::from_io("smart_log", &dev_path, &e)),
         }
     }
-    results
+    (results, errors)
 }
@@ -0,0 +1,153 @@
+// src/collector/pressure.rs
+//! Pressure stall information (PSI) from /proc/pressure and, when cgroup v2 is mounted, from the
+//! `*.pressure` files of individual cgroups. One record per scope: `/` for the whole system, the
+//! cgroup path otherwise.
+
+use crate::collector::error::CollectorError;
+use crate::collector::filter::Filter;
+use serde_json::{Map, Value, json};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Resources the kernel reports pressure for.
+pub const RESOURCES: [&str; 4] = ["cpu", "memory", "io", "irq"];
+
+/// How deep below the root cgroups are walked unless configured otherwise
+/// (`/system.slice/foo.service` is two levels deep).
+pub const DEFAULT_CGROUP_DEPTH: usize = 2;
+
+/// errno for "operation not supported", returned when reading pressure files of a kernel booted
+/// with `psi=0`; not every Rust version maps it to `ErrorKind::Unsupported`.
+const EOPNOTSUPP: i32 = 95;
+
+/// Function to parse a pressure file into fields such as `memory_some_avg10` and
+/// `memory_full_total_us` (total stall time in microseconds).
+pub fn parse_pressure(resource: &str, text: &str, record: &mut Map<String, Value>) {
+    for line in text.lines() {
+        let mut parts = line.split_whitespace();
+        let Some(kind @ ("some" | "full")) = parts.next() else {
+            continue;
+        };
+        for part in parts {
+            let Some((name, value)) = part.split_once('=') else {
+                continue;
+            };
+            let field = match name {
+                "total" => format!("{}_{}_total_us", resource, kind),
+                name => format!("{}_{}_{}", resource, kind, name),
+            };
+            let value = match name {
+                "total" => value.parse::<u64>().ok().map(|v| json!(v)),
+                _ => value.parse::<f64>().ok().map(|v| json!(v)),
+            };
+            if let Some(value) = value {
+                record.insert(field, value);
+            }
+        }
+    }
+}
+
+/// Function to check whether a read failed because the kernel has no PSI for the resource
+/// (file missing, or PSI disabled with `psi=0`).
+fn unsupported(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::NotFound | io::ErrorKind::Unsupported
+    ) || e.raw_os_error() == Some(EOPNOTSUPP)
+}
+
+/// Function to read `<resource><suffix>` for every resource in `dir` into one record.
+/// Returns `None` when the directory has no pressure files at all.
+fn read_scope(
+    dir: &Path,
+    suffix: &str,
+    scope: &str,
+    errors: &mut Vec<CollectorError>,
+) -> Option<Value> {
+    let mut record = Map::new();
+    record.insert("cgroup".to_string(), json!(scope));
+    let mut found = false;
+    for resource in RESOURCES {
+        let path = dir.join(format!("{}{}", resource, suffix));
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                found = true;
+                parse_pressure(resource, &text, &mut record);
+            }
+            Err(e) if unsupported(&e) => {}
+            Err(e) => errors.push(CollectorError::from_io("pressure", scope, &e)),
+        }
+    }
+    found.then_some(Value::Object(record))
+}
+
+/// Function to find where cgroup v2 is mounted from the contents of /proc/self/mounts.
+pub fn cgroup2_mount(mounts: &str) -> Option<PathBuf> {
+    mounts.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        (fields.get(2) == Some(&"cgroup2")).then(|| PathBuf::from(fields[1]))
+    })
+}
+
+/// Function to collect the pressure of every cgroup the filter allows, walking at most
+/// `max_depth` levels below `root` so large hierarchies (e.g. Kubernetes nodes) stay cheap.
+pub fn read_cgroup_pressure(
+    root: &Path,
+    cgroups: &Filter,
+    max_depth: usize,
+) -> (Vec<Value>, Vec<CollectorError>) {
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    let mut pending = vec![(root.to_path_buf(), String::new(), 0)];
+    while let Some((dir, scope, depth)) = pending.pop() {
+        if depth > 0
+            && cgroups.allows(&scope)
+            && let Some(record) = read_scope(&dir, ".pressure", &scope, &mut errors)
+        {
+            records.push(record);
+        }
+        if depth >= max_depth {
+            continue;
+        }
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            // Cgroups come and go while they are walked.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                errors.push(CollectorError::from_io("pressure", &scope, &e));
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                pending.push((entry.path(), format!("{}/{}", scope, name), depth + 1));
+            }
+        }
+    }
+    records.sort_by(|a, b| a["cgroup"].as_str().cmp(&b["cgroup"].as_str()));
+    (records, errors)
+}
+
+/// Function to collect system-wide and per-cgroup pressure along with collection errors.
+/// Kernels without PSI yield no records rather than errors.
+pub fn get_pressure_with_errors(
+    cgroups: &Filter,
+    max_depth: usize,
+) -> (Vec<Value>, Vec<CollectorError>) {
+    let mut errors = Vec::new();
+    let mut records: Vec<Value> = read_scope(Path::new("/proc/pressure"), "", "/", &mut errors)
+        .into_iter()
+        .collect();
+
+    if let Some(root) = fs::read_to_string("/proc/self/mounts")
+        .ok()
+        .and_then(|mounts| cgroup2_mount(&mounts))
+    {
+        let (cgroup_records, cgroup_errors) = read_cgroup_pressure(&root, cgroups, max_depth);
+        records.extend(cgroup_records);
+        errors.extend(cgroup_errors);
+    }
+    (records, errors)
+}
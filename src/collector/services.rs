@@ -1,16 +1,29 @@
 // src/collector/services.rs
 //! systemd service status collection.
 
+use crate::collector::error::{CollectorError, ErrorKind};
 use serde_json::{Value, json};
 use std::process::Command;
-use std::str;
 
 /// Function to extract status of a list of services.
 pub fn get_service_status(services: &[String]) -> Vec<Value> {
+    get_service_status_with_errors(services).0
+}
+
+/// Function to extract status of a list of services along with the ones that could not be queried.
+/// Services that could not be queried are reported with status "unknown".
+pub fn get_service_status_with_errors(services: &[String]) -> (Vec<Value>, Vec<CollectorError>) {
     let mut results = Vec::new();
+    let mut errors = Vec::new();
 
     for service in services {
-        let status = get_service_active_status(service);
+        let status = match get_service_active_status(service) {
+            Ok(status) => status,
+            Err(e) => {
+                errors.push(e);
+                "unknown".to_string()
+            }
+        };
 
         results.push(json!({
             "service_name": service,
@@ -18,19 +31,27 @@ pub fn get_service_status(services: &[String]) -> Vec<Value> {
         }));
     }
 
-    results
+    (results, errors)
 }
 
 /// Function to check whether a service is active or not.
-fn get_service_active_status(service: &str) -> String {
-    match Command::new("systemctl")
+fn get_service_active_status(service: &str) -> Result<String, CollectorError> {
+    let output = Command::new("systemctl")
         .args(["is-active", service])
         .output()
-    {
-        Ok(output) => str::from_utf8(&output.stdout)
-            .unwrap_or("unknown")
-            .trim()
-            .to_string(),
-        Err(_) => "error".to_string(),
+        .map_err(|e| CollectorError::from_io("services", service, &e))?;
+
+    // is-active exits non-zero for inactive units, so only an empty answer is a failure.
+    let status = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if status.is_empty() {
+        return Err(CollectorError {
+            instance: Some(service.to_string()),
+            ..CollectorError::new(
+                "services",
+                ErrorKind::Command,
+                String::from_utf8_lossy(&output.stderr).trim(),
+            )
+        });
     }
+    Ok(status)
 }
@@ -1,6 +1,8 @@
 // src/collector/sys.rs
 //! System-level info: timestamp, hostname, uptime, cpu freq, top-level sysinfo.
 
+use crate::collector::error::{CollectorError, ErrorKind};
+use crate::collector::metric::CollectorConfig;
 use serde_json::{Value, json};
 use sysinfo::System;
 
@@ -25,6 +27,7 @@ fn uptime_raw() -> String {
 }
 
 /// Function t get extract raw cpu freq string.
+/// Only the first core; per-core frequencies are reported under `cpufreq`.
 fn cpu_freq_raw(sys: &System) -> String {
     let cpu_freq = sys.cpus().first().map(|cpu| cpu.frequency()).unwrap_or(0);
     cpu_freq.to_string()
@@ -32,15 +35,40 @@ fn cpu_freq_raw(sys: &System) -> String {
 
 /// Function to extract top level system information.
 pub fn get_sysinfo(sys: &System) -> Value {
-    json!({
+    get_sysinfo_with_errors(sys, &CollectorConfig::default()).0
+}
+
+/// Function to extract top level system information along with collection errors.
+pub fn get_sysinfo_with_errors(
+    sys: &System,
+    config: &CollectorConfig,
+) -> (Value, Vec<CollectorError>) {
+    let (smart_log, mut errors) = crate::collector::nvme::collect_smart_log_filtered(&config.nvme);
+    let smart_log = serde_json::to_value(smart_log).unwrap_or_else(|e| {
+        errors.push(CollectorError::new(
+            "smart_log",
+            ErrorKind::Serialization,
+            e,
+        ));
+        json!([])
+    });
+    let (cpufreq, cpufreq_errors) = crate::collector::cpufreq::get_cpufreq_with_errors(sys);
+    errors.extend(cpufreq_errors);
+
+    let info = json!({
         "timestamp": get_timestamp(),
         "hostname": get_hostname(),
         "uptime": uptime_raw(),
         "cpu_freq_mhz": cpu_freq_raw(sys),
-        "disk_usage": crate::collector::disk::get_disk_usage(),
-        "network": crate::collector::net::get_if_data(),
-        "smart_log": crate::collector::nvme::collect_smart_log(),
-    })
+        "cpufreq": cpufreq,
+        "disk_usage": crate::collector::disk::get_disk_usage_filtered(
+            &config.disk_mounts,
+            &config.disk_fstypes
+        ),
+        "network": crate::collector::net::get_if_data_filtered(&config.interfaces),
+        "smart_log": smart_log,
+    });
+    (info, errors)
 }
 
 /// Wrapper function for uptime.
@@ -1 +1,12 @@
+pub mod aggregate;
+pub mod alert;
+pub mod check;
 pub mod collector;
+pub mod config;
+pub mod notify;
+pub mod output;
+pub mod rate;
+pub mod schedule;
+pub mod stats;
+pub mod store;
+pub mod transform;
@@ -1,135 +1,777 @@
 //! Main module for tinycollectd.
-use clap::{Parser, ValueEnum};
-use serde_json::{Map, Value, json};
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use serde_json::Value;
+use std::ffi::OsString;
+use std::io::Write;
 use std::net::{Ipv4Addr, SocketAddrV4};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
-use sysinfo::System;
-use tinycollectd::collector;
+use tinycollectd::aggregate::Aggregator;
+use tinycollectd::alert::{self, AlertEngine, Rule};
+use tinycollectd::check;
+use tinycollectd::collector::{self, CollectorConfig, Filter, MetricType, Pattern};
+use tinycollectd::config;
+use tinycollectd::notify::Notifier;
+use tinycollectd::output::{
+    self, ElasticConfig, ElasticSink, HttpConfig, HttpSink, JOURNAL_SOCKET, JournaldSink,
+    LogFilter, SyslogSink, SyslogTransport,
+};
+use tinycollectd::rate::Rates;
+use tinycollectd::schedule::{self, MissedTick, Timing};
+use tinycollectd::stats::STATS;
+use tinycollectd::store::{self, Store};
+use tinycollectd::transform::{self, Transform};
 use tokio::net::UdpSocket;
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::{mpsc, watch};
 
 #[derive(ValueEnum, Clone, Debug, PartialEq)]
 enum OutputMode {
     Udp,
     Stdout,
     Both,
+    Http,
+    Elasticsearch,
+    Syslog,
+    Journald,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// run a single Nagios/Icinga-compatible check, print its status line and exit with its state
+    #[command(subcommand)]
+    Check(Check),
+    /// print the history kept by --store for one metric
+    Query {
+        /// metric as <collector>.<field> (e.g. disk.used_percent, network.rx_bytes)
+        #[arg(long)]
+        metric: String,
+        /// how far back to look (e.g. 90s, 30m, 2h, 7d)
+        #[arg(long, default_value = "1h", value_parser = store::parse_duration)]
+        since: u64,
+        /// only show this disk, interface, controller or service
+        #[arg(long)]
+        instance: Option<String>,
+        /// draw one sparkline per instance instead of a table
+        #[arg(long)]
+        sparkline: bool,
+    },
+}
+
+/// Function to print stored samples of a metric as a table or as sparklines.
+fn query(
+    dir: &std::path::Path,
+    metric: &str,
+    since: u64,
+    instance: Option<&str>,
+    sparkline: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let store = Store::open_existing(dir).map_err(|e| e.to_string())?;
+    let from = collector::get_timestamp().saturating_sub(since);
+    let samples: Vec<store::Sample> = store::select(&store.read_since(from)?, metric)
+        .into_iter()
+        .filter(|s| instance.is_none_or(|i| s.instance == i))
+        .collect();
+    if samples.is_empty() {
+        return Err(format!("no samples of {} in the last {}s", metric, since).into());
+    }
+
+    let width = samples
+        .iter()
+        .map(|s| s.instance.len())
+        .max()
+        .unwrap_or(0)
+        .max("INSTANCE".len());
+    if sparkline {
+        let mut instances: Vec<&str> = samples.iter().map(|s| s.instance.as_str()).collect();
+        instances.sort();
+        instances.dedup();
+        for name in instances {
+            let values: Vec<f64> = samples
+                .iter()
+                .filter(|s| s.instance == name)
+                .map(|s| s.value)
+                .collect();
+            let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            println!(
+                "{:width$}  {}  min {:.2} max {:.2} last {:.2}",
+                name,
+                store::sparkline(&values, 60),
+                min,
+                max,
+                values[values.len() - 1],
+            );
+        }
+    } else {
+        println!("{:20}  {:width$}  VALUE", "TIME", "INSTANCE");
+        for sample in samples {
+            println!(
+                "{:20}  {:width$}  {}",
+                output::rfc3339(sample.timestamp),
+                sample.instance,
+                sample.value
+            );
+        }
+    }
+    Ok(())
+}
+
+#[derive(Subcommand)]
+enum Check {
+    /// used space of mounted filesystems
+    Disk {
+        /// used percent at which the check turns WARNING
+        #[arg(long, default_value = "80")]
+        warn: f64,
+        /// used percent at which the check turns CRITICAL
+        #[arg(long, default_value = "90")]
+        crit: f64,
+        /// mount points to check [default: all]
+        mounts: Vec<String>,
+    },
+    /// NVMe SMART health (critical warning and media errors)
+    Smart,
+    /// systemd unit state
+    Service {
+        #[arg(required = true)]
+        services: Vec<String>,
+    },
+}
+
+impl Check {
+    /// Function to run the check and print its status line, returning the plugin exit code.
+    /// The collector filters given on the command line apply to checks as well.
+    fn run(&self, config: &CollectorConfig) -> i32 {
+        let result = match self {
+            Check::Disk { warn, crit, mounts } => {
+                let disks =
+                    collector::get_disk_usage_filtered(&config.disk_mounts, &config.disk_fstypes);
+                check::check_disk(&disks, mounts, *warn, *crit)
+            }
+            Check::Smart => {
+                let (logs, errors) = collector::collect_smart_log_filtered(&config.nvme);
+                check::check_smart(&logs, &errors)
+            }
+            Check::Service { services } => {
+                check::check_service(&collector::get_service_status(services))
+            }
+        };
+        println!("{}", result.line());
+        result.status.exit_code()
+    }
 }
 
 #[derive(Parser)]
 struct Cli {
-    /// output mode (udp, stdout)
-    #[arg(long, value_enum, default_value = "udp")]
-    output: OutputMode,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// output modes, comma separated (udp, stdout, http, elasticsearch, syslog, journald)
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "udp")]
+    output: Vec<OutputMode>,
 
     /// destination for metrics (e.g. 127.0.0.1:1555)
     #[arg(long, default_value_t = SocketAddrV4::new(Ipv4Addr::new(127,0,0,1), 1555))]
     destination: SocketAddrV4,
     /// metrics tinycollectd would collect
-    #[arg(long, value_enum, value_delimiter = ',', default_value = "All")]
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "all")]
     metrics: Vec<MetricType>,
-    /// list of services to pull status
+    /// list of services to pull status (collected by the services metric)
     #[arg(long)]
     services: Vec<String>,
+    /// mount points to collect disk usage for, comma separated globs (or ~regex)
+    #[arg(long, value_parser = collector::parse_pattern, value_delimiter = ',')]
+    disk_include: Vec<Pattern>,
+    /// mount points to leave out (e.g. '/snap/*,/run/*')
+    #[arg(long, value_parser = collector::parse_pattern, value_delimiter = ',')]
+    disk_exclude: Vec<Pattern>,
+    /// file system types to collect disk usage for (e.g. ext4,xfs)
+    #[arg(long, value_parser = collector::parse_pattern, value_delimiter = ',')]
+    disk_include_fstype: Vec<Pattern>,
+    /// file system types to leave out (e.g. tmpfs,overlay,squashfs)
+    #[arg(long, value_parser = collector::parse_pattern, value_delimiter = ',')]
+    disk_exclude_fstype: Vec<Pattern>,
+    /// interfaces to collect (e.g. 'eth*,en*')
+    #[arg(long, value_parser = collector::parse_pattern, value_delimiter = ',')]
+    net_include: Vec<Pattern>,
+    /// interfaces to leave out (e.g. 'lo,veth*,docker*')
+    #[arg(long, value_parser = collector::parse_pattern, value_delimiter = ',')]
+    net_exclude: Vec<Pattern>,
+    /// NVMe controllers to read the smart log from (e.g. nvme0)
+    #[arg(long, value_parser = collector::parse_pattern, value_delimiter = ',')]
+    nvme_include: Vec<Pattern>,
+    /// NVMe controllers to leave out
+    #[arg(long, value_parser = collector::parse_pattern, value_delimiter = ',')]
+    nvme_exclude: Vec<Pattern>,
+    /// cgroups to report pressure for, by path (e.g. '/system.slice/*')
+    #[arg(long, value_parser = collector::parse_pattern, value_delimiter = ',')]
+    cgroup_include: Vec<Pattern>,
+    /// cgroups to leave out (e.g. '/user.slice*')
+    #[arg(long, value_parser = collector::parse_pattern, value_delimiter = ',')]
+    cgroup_exclude: Vec<Pattern>,
+    /// levels of the cgroup hierarchy walked for pressure (/system.slice/foo.service is 2);
+    /// raise it to reach deeper cgroups such as containers, at the cost of a larger walk
+    #[arg(long, default_value = "2")]
+    cgroup_depth: usize,
     /// interval for data to be collected in seconds.
     #[arg(long, default_value = "10")]
     collection_interval: u64,
+    /// per-collector interval overriding --collection-interval (e.g. smart-log=300, network=1);
+    /// setting one for a collector covered by `all` splits `all` into individual collectors
+    #[arg(long = "interval", value_parser = schedule::parse_interval)]
+    intervals: Vec<(MetricType, u64)>,
+    /// per-collector deadline in seconds (e.g. smart-log=5); a cycle is published without
+    /// collectors that miss theirs [default: the collector's interval]
+    #[arg(long = "timeout", value_parser = schedule::parse_interval)]
+    timeouts: Vec<(MetricType, u64)>,
+    /// how to handle collections that overran their interval
+    #[arg(long, value_enum, default_value = "skip")]
+    missed_tick: MissedTick,
+    /// seconds to wait on SIGTERM for running collections to finish before exiting without them;
+    /// keep it below the service manager's stop timeout (90s by default for systemd)
+    #[arg(long, default_value = "30")]
+    shutdown_timeout: u64,
+    /// upper bound in seconds for a random per-host offset added to every collection boundary
+    #[arg(long, default_value = "0")]
+    splay: u64,
+    /// collect once right away, send to the outputs and exit (non-zero if a collector failed)
+    #[arg(long, conflicts_with = "count")]
+    once: bool,
+    /// like --once, but run every collector N times at its interval before exiting
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    count: Option<u64>,
+    /// endpoint for the http output (e.g. https://metrics.example.com/ingest)
+    #[arg(long)]
+    http_url: Option<String>,
+    /// header added to every http request (e.g. "Authorization: Bearer abc")
+    #[arg(long, value_parser = output::parse_header)]
+    http_header: Vec<(String, String)>,
+    /// number of collection cycles sent per http request
+    #[arg(long, default_value = "1")]
+    http_batch: usize,
+    /// http and elasticsearch request timeout in seconds
+    #[arg(long, default_value = "5")]
+    http_timeout: u64,
+    /// retries on http and elasticsearch 429/5xx responses and connection errors
+    #[arg(long, default_value = "3")]
+    http_retries: u32,
+    /// file to keep undeliverable http batches in until the endpoint recovers
+    #[arg(long)]
+    http_spool: Option<PathBuf>,
+    /// maximum size of the http spool file in bytes
+    #[arg(long, default_value = "67108864")]
+    http_spool_max_bytes: u64,
+    /// base url of the elasticsearch/opensearch cluster (e.g. https://localhost:9200)
+    #[arg(long)]
+    es_url: Option<String>,
+    /// index prefix, documents are written to <prefix>-YYYY.MM.DD
+    #[arg(long, default_value = "tinycollectd")]
+    es_index: String,
+    /// basic auth user for elasticsearch
+    #[arg(long)]
+    es_user: Option<String>,
+    /// basic auth password for elasticsearch
+    #[arg(long)]
+    es_password: Option<String>,
+    /// syslog destination (udp://host:514, tcp://host:514 or unix:///dev/log)
+    #[arg(long, value_parser = output::parse_syslog_address, default_value = "unix:///dev/log")]
+    syslog_address: SyslogTransport,
+    /// IANA private enterprise number used in the structured data IDs of syslog messages
+    /// (tinycollectd@<number>); without one, fields are only sent in the message text
+    #[arg(long)]
+    syslog_enterprise_number: Option<u32>,
+    /// disk used percent at which syslog/journald entries are raised to warning
+    #[arg(long, default_value = "90")]
+    log_disk_warn: f64,
+    /// only write warning and critical records to syslog/journald
+    #[arg(long)]
+    log_problems_only: bool,
+    /// host label added to every payload (e.g. dc=ams1, role=db); repeat for more labels
+    #[arg(long = "label", value_parser = output::parse_label)]
+    labels: Vec<(String, String)>,
+    /// add per-second rates next to cumulative counters (interface bytes, NVMe data units and
+    /// commands); the first cycle only records a baseline
+    #[arg(long)]
+    rates: bool,
+    /// processing step applied to every payload before the outputs, in order: rename <path> <name>,
+    /// drop <path>, relabel <source> <regex> <target> <replacement>, convert <path> <from> <to>
+    /// (e.g. "convert smart_log.temperature kelvin celsius")
+    #[arg(long = "transform", value_parser = transform::parse_transform)]
+    transforms: Vec<Transform>,
+    /// send one payload per window of this many seconds with min/max/avg/p95 next to the last
+    /// value of every numeric field, instead of every collected sample (alerts still see every sample)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    aggregate: Option<u64>,
+    /// directory keeping a compressed history of every payload sent, for `tinycollectd query`
+    #[arg(long)]
+    store: Option<PathBuf>,
+    /// maximum size of the --store directory in bytes; the oldest history is removed first
+    #[arg(long, default_value = "67108864")]
+    store_max_bytes: u64,
+    /// alert rule sent to the outputs when it starts or stops holding
+    /// (e.g. "disk_usage.used_percent > 90 for 3", "services.status != active")
+    #[arg(long = "alert", value_parser = alert::parse_rule)]
+    alerts: Vec<Rule>,
+    /// TOML file setting any of the above by field name (e.g. collection_interval = 30);
+    /// flags given on the command line take precedence over the file
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// print the effective configuration (defaults, file and flags merged) as TOML, with
+    /// credentials redacted, and exit
+    #[arg(long)]
+    print_config: bool,
 }
-#[derive(ValueEnum, Clone, Debug, PartialEq)]
-enum MetricType {
-    All,
-    DiskUsage,
-    Network,
-    Cpufreq,
-    Uptime,
-    SmartLog,
+
+impl Cli {
+    /// Function to gather the settings collectors need beyond the list of metrics.
+    fn collector_config(&self) -> CollectorConfig {
+        let filter = |include: &[Pattern], exclude: &[Pattern]| {
+            Filter::new(include.to_vec(), exclude.to_vec())
+        };
+        CollectorConfig {
+            services: self.services.clone(),
+            disk_mounts: filter(&self.disk_include, &self.disk_exclude),
+            disk_fstypes: filter(&self.disk_include_fstype, &self.disk_exclude_fstype),
+            interfaces: filter(&self.net_include, &self.net_exclude),
+            nvme: filter(&self.nvme_include, &self.nvme_exclude),
+            cgroups: filter(&self.cgroup_include, &self.cgroup_exclude),
+            cgroup_depth: Some(self.cgroup_depth),
+        }
+    }
+
+    /// Function to get the number of cycles to run before exiting, if not running forever.
+    fn cycles(&self) -> Option<u64> {
+        self.count.or(self.once.then_some(1))
+    }
+
+    /// Function to check whether an output is enabled, `both` meaning udp and stdout.
+    fn has_output(&self, mode: OutputMode) -> bool {
+        self.output.contains(&mode)
+            || (matches!(mode, OutputMode::Udp | OutputMode::Stdout)
+                && self.output.contains(&OutputMode::Both))
+    }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
+/// Destinations every payload is written to.
+struct Outputs {
+    stdout: bool,
+    udp: Option<(Arc<UdpSocket>, SocketAddrV4)>,
+    http: Option<HttpSink>,
+    elastic: Option<ElasticSink>,
+    syslog: Option<SyslogSink>,
+    journald: Option<JournaldSink>,
+    store: Option<Store>,
+    labels: Vec<(String, String)>,
+    rates: Option<Rates>,
+    transforms: Vec<Transform>,
+    alerts: AlertEngine,
+    aggregator: Option<Aggregator>,
+}
 
-    let socket = UdpSocket::bind("0.0.0.0:0").await?;
-    let mut sys = System::new_all();
+impl Outputs {
+    /// Function to set up the outputs selected on the command line. The UDP socket is
+    /// created once by the caller so it survives configuration reloads.
+    fn new(cli: &Cli, socket: &Arc<UdpSocket>) -> Result<Self, Box<dyn std::error::Error>> {
+        let udp = cli
+            .has_output(OutputMode::Udp)
+            .then(|| (socket.clone(), cli.destination));
 
-    loop {
-        sys.refresh_all();
+        let http = if cli.has_output(OutputMode::Http) {
+            let url = cli
+                .http_url
+                .clone()
+                .ok_or("--http-url is required for the http output")?;
+            Some(HttpSink::new(HttpConfig {
+                url,
+                headers: cli.http_header.clone(),
+                batch_size: cli.http_batch,
+                timeout: Duration::from_secs(cli.http_timeout),
+                retries: cli.http_retries,
+                spool: cli.http_spool.clone(),
+                spool_max_bytes: cli.http_spool_max_bytes,
+            })?)
+        } else {
+            None
+        };
 
-        let metrics_value = if cli.metrics.contains(&MetricType::All) {
-            collector::get_sysinfo(&sys)
+        let elastic = if cli.has_output(OutputMode::Elasticsearch) {
+            let url = cli
+                .es_url
+                .clone()
+                .ok_or("--es-url is required for the elasticsearch output")?;
+            Some(ElasticSink::new(ElasticConfig {
+                url,
+                index_prefix: cli.es_index.clone(),
+                username: cli.es_user.clone(),
+                password: cli.es_password.clone(),
+                timeout: Duration::from_secs(cli.http_timeout),
+                retries: cli.http_retries,
+            })?)
         } else {
-            let mut metrics_obj = Map::new();
+            None
+        };
 
-            if cli.metrics.contains(&MetricType::SmartLog) {
-                let smart_log = collector::collect_smart_log();
-                let smart_val = serde_json::to_value(smart_log).unwrap_or_else(|_| json!([]));
-                metrics_obj.insert("smart_log".to_string(), smart_val);
-            }
+        let log_filter = LogFilter {
+            disk_warn_percent: cli.log_disk_warn,
+            problems_only: cli.log_problems_only,
+        };
+        let syslog = cli.has_output(OutputMode::Syslog).then(|| {
+            SyslogSink::new(
+                cli.syslog_address.clone(),
+                log_filter.clone(),
+                cli.syslog_enterprise_number,
+            )
+        });
+        let journald = cli
+            .has_output(OutputMode::Journald)
+            .then(|| JournaldSink::new(JOURNAL_SOCKET.into(), log_filter.clone()));
 
-            if cli.metrics.contains(&MetricType::DiskUsage) {
-                let disk_data = collector::get_disk_usage(); // Vec<Value>
-                metrics_obj.insert("disk_usage".to_string(), Value::Array(disk_data));
-            }
+        Ok(Self {
+            stdout: cli.has_output(OutputMode::Stdout),
+            udp,
+            http,
+            elastic,
+            syslog,
+            journald,
+            store: cli
+                .store
+                .as_deref()
+                .map(|dir| Store::open(dir, cli.store_max_bytes))
+                .transpose()?,
+            labels: cli.labels.clone(),
+            rates: cli.rates.then(Rates::default),
+            transforms: cli.transforms.clone(),
+            alerts: AlertEngine::new(cli.alerts.clone()),
+            aggregator: cli.aggregate.map(Aggregator::new),
+        })
+    }
 
-            if cli.metrics.contains(&MetricType::Network) {
-                let network_data = collector::get_if_data(); // Vec<Value>
-                metrics_obj.insert("network".to_string(), Value::Array(network_data));
-            }
+    /// Function to run a payload through labels, rates, transforms, alerts and aggregation, then
+    /// write it and the alerts it changed to every enabled output.
+    async fn send(&mut self, mut payload: Value) {
+        output::apply_labels(&mut payload, &self.labels);
+        if let Some(rates) = self.rates.as_mut() {
+            rates.derive(&mut payload);
+        }
+        transform::apply_transforms(&self.transforms, &mut payload);
+        let alerts = self.alerts.evaluate(&payload);
+        let payload = match self.aggregator.as_mut() {
+            Some(aggregator) => aggregator.push(payload),
+            None => Some(payload),
+        };
+        if let Some(payload) = payload {
+            self.write(payload).await;
+        }
+        if let Some(alerts) = alerts {
+            self.write(alerts).await;
+        }
+    }
 
-            if cli.metrics.contains(&MetricType::Cpufreq) {
-                let cpu_data = collector::cpu_freq_json(&sys);
-                if let Value::Object(map) = cpu_data {
-                    metrics_obj.insert("cpufreq".to_string(), Value::Object(map));
-                }
-            }
+    /// Function to write a payload to every enabled output.
+    async fn write(&mut self, payload: Value) {
+        if self.stdout {
+            println!("{}", serde_json::to_string_pretty(&payload).unwrap());
+        }
 
-            if cli.metrics.contains(&MetricType::Uptime) {
-                let uptime_data = collector::uptime_json();
-                if let Value::Object(map) = uptime_data {
-                    metrics_obj.insert("uptime".to_string(), Value::Object(map));
-                }
+        if let Some((socket, destination)) = &self.udp {
+            let bytes = serde_json::to_vec(&payload).unwrap();
+            if let Err(e) = socket.send_to(&bytes, destination).await {
+                eprintln!("Failed to send UDP packet: {}", e);
+                STATS.record_failure();
+                STATS.record_dropped(1);
+            } else {
+                println!("Sent metrics to {} ({} bytes)", destination, bytes.len());
+                STATS.record_sent(bytes.len(), 1);
             }
+        }
 
-            Value::Object(metrics_obj)
+        if let Some(sink) = &self.elastic {
+            sink.send(&payload).await;
+        }
+
+        if let Some(sink) = self.syslog.as_mut() {
+            sink.send(&payload).await;
+        }
+
+        if let Some(sink) = self.journald.as_mut() {
+            sink.send(&payload).await;
+        }
+
+        if let Some(store) = &self.store
+            && let Err(e) = store.append(&payload)
+        {
+            eprintln!("Failed to write to the store: {}", e);
+        }
+
+        if let Some(sink) = self.http.as_mut() {
+            sink.push(payload).await;
+        }
+    }
+
+    /// Function to send anything still buffered, including a partial aggregation window
+    /// (spooling it if the endpoint is down).
+    async fn flush(&mut self) {
+        if let Some(payload) = self.aggregator.as_mut().and_then(Aggregator::take) {
+            self.write(payload).await;
+        }
+        if let Some(sink) = self.http.as_mut() {
+            sink.flush().await;
+        }
+    }
+}
+
+/// Collector groups scheduled from one configuration, stopped together.
+struct Collectors {
+    stop: watch::Sender<bool>,
+    /// Interval of the most frequently collected group.
+    shortest: Duration,
+    /// How long running collections may take to finish on shutdown.
+    shutdown_timeout: Duration,
+    /// Number of groups whose collectors are overrunning their deadlines.
+    stalled: Arc<AtomicUsize>,
+}
+
+impl Collectors {
+    /// Function to plan and spawn the collector groups for a configuration.
+    fn spawn(cli: &Cli, tx: &mpsc::Sender<Value>) -> Result<Self, Box<dyn std::error::Error>> {
+        let groups = schedule::plan(&cli.metrics, cli.collection_interval, &cli.intervals)?;
+        let timing = Timing {
+            missed_tick: cli.missed_tick,
+            splay: schedule::random_splay(Duration::from_secs(cli.splay)),
+            timeouts: cli
+                .timeouts
+                .iter()
+                .map(|(metric, secs)| (metric.clone(), Duration::from_secs(*secs)))
+                .collect(),
+            cycles: cli.cycles(),
         };
+        let config = Arc::new(cli.collector_config());
 
-        let combined = json!({
-            "timestamp": collector::get_timestamp(),
-            "hostname": collector::get_hostname(),
-            "metrics": metrics_value,
-        });
+        let shortest = groups
+            .iter()
+            .map(|group| group.interval)
+            .min()
+            .unwrap_or_default();
+
+        let (stop, stopped) = watch::channel(false);
+        let stalled = Arc::new(AtomicUsize::new(0));
+        for group in groups {
+            tokio::spawn(schedule::run_group(
+                group,
+                timing.clone(),
+                config.clone(),
+                tx.clone(),
+                stopped.clone(),
+                stalled.clone(),
+            ));
+        }
+        Ok(Self {
+            stop,
+            shortest,
+            shutdown_timeout: Duration::from_secs(cli.shutdown_timeout),
+            stalled,
+        })
+    }
+
+    /// Function to let every group finish its current cycle and exit.
+    fn stop(&self) {
+        let _ = self.stop.send(true);
+    }
+
+    /// Function to check whether any group's last cycle had a collector overrun its deadline.
+    fn stalled(&self) -> bool {
+        self.stalled.load(Ordering::SeqCst) > 0
+    }
+}
+
+/// Function to read the configuration, called at startup and again on SIGHUP.
+/// Values from `--config` are turned into flags placed before the real ones, leaving out
+/// every flag the command line already sets.
+fn load_cli() -> Result<(Cli, ArgMatches), Box<dyn std::error::Error>> {
+    let args: Vec<OsString> = std::env::args_os().collect();
+    let matches = Cli::command().try_get_matches_from(&args)?;
+    let cli = Cli::from_arg_matches(&matches)?;
+    let Some(path) = &cli.config else {
+        return Ok((cli, matches));
+    };
+
+    let table = config::load(path)?;
+    let mut merged: Vec<OsString> = args.iter().take(1).cloned().collect();
+    merged.extend(config::file_args(&Cli::command(), &matches, &table)?);
+    merged.extend(args.iter().skip(1).cloned());
+
+    let matches = Cli::command().try_get_matches_from(merged)?;
+    Ok((Cli::from_arg_matches(&matches)?, matches))
+}
+
+/// Function to reload the configuration, keeping the old one if the new one is invalid.
+async fn reload(
+    outputs: &mut Outputs,
+    collectors: &mut Collectors,
+    socket: &Arc<UdpSocket>,
+    tx: &mpsc::Sender<Value>,
+    notifier: &Notifier,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (cli, _) = load_cli()?;
+    let mut new_outputs = Outputs::new(&cli, socket)?;
+    // Spawn first so an invalid schedule leaves the running collectors alone.
+    let new_collectors = Collectors::spawn(&cli, tx)?;
 
-        let bytes = serde_json::to_vec(&combined).unwrap();
+    collectors.stop();
+    *collectors = new_collectors;
+    outputs.flush().await;
+    // Carry state over so alerts that keep firing are not announced again and rates continue.
+    new_outputs.alerts = std::mem::take(&mut outputs.alerts);
+    new_outputs.alerts.set_rules(cli.alerts);
+    if let Some(rates) = outputs.rates.take()
+        && new_outputs.rates.is_some()
+    {
+        new_outputs.rates = Some(rates);
+    }
+    *outputs = new_outputs;
+    check_watchdog(notifier, collectors);
+    Ok(())
+}
+
+/// Function to warn when the watchdog would fire before the first collection cycle completes.
+fn check_watchdog(notifier: &Notifier, collectors: &Collectors) {
+    if let Some(watchdog) = notifier.watchdog()
+        && collectors.shortest >= watchdog
+    {
+        eprintln!(
+            "Watchdog timeout {:?} is not longer than the shortest collection interval {:?}",
+            watchdog, collectors.shortest
+        );
+    }
+}
+
+/// Function to publish payloads until every group has run its cycles, then exit
+/// non-zero if any collector reported an error.
+async fn run_cycles(
+    mut outputs: Outputs,
+    mut rx: mpsc::Receiver<Value>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut failures = 0;
+    while let Some(payload) = rx.recv().await {
+        failures += payload["errors"].as_array().map_or(0, Vec::len);
+        outputs.send(payload).await;
+    }
+    outputs.flush().await;
+
+    if failures > 0 {
+        eprintln!("{} collector error(s) reported", failures);
+    }
+    exit(i32::from(failures > 0));
+}
+
+/// Function to end the process without waiting for the runtime to shut down: a collector that
+/// missed its deadline still occupies a blocking thread, which dropping the runtime waits for.
+fn exit(code: i32) -> ! {
+    let _ = std::io::stdout().flush();
+    std::process::exit(code);
+}
 
-        match cli.output {
-            OutputMode::Udp => {
-                if let Err(e) = socket.send_to(&bytes, cli.destination).await {
-                    eprintln!("Failed to send UDP packet: {}", e);
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let (cli, matches) = match load_cli() {
+        Ok(loaded) => loaded,
+        Err(e) => match e.downcast::<clap::Error>() {
+            Ok(e) => e.exit(),
+            Err(e) => return Err(e),
+        },
+    };
+    if cli.print_config {
+        print!("{}", config::render(&Cli::command(), &matches));
+        return Ok(());
+    }
+    match &cli.command {
+        Some(Command::Check(check)) => std::process::exit(check.run(&cli.collector_config())),
+        Some(Command::Query {
+            metric,
+            since,
+            instance,
+            sparkline,
+        }) => {
+            let dir = cli
+                .store
+                .as_deref()
+                .ok_or("--store is required to query history")?;
+            return query(dir, metric, *since, instance.as_deref(), *sparkline);
+        }
+        None => {}
+    }
+
+    let socket = Arc::new(UdpSocket::bind("0.0.0.0:0").await?);
+    let mut outputs = Outputs::new(&cli, &socket)?;
+
+    // Every group publishes its payloads here as soon as they are collected.
+    let (tx, mut rx) = mpsc::channel(64);
+    let mut collectors = Collectors::spawn(&cli, &tx)?;
+
+    if cli.cycles().is_some() {
+        drop(tx);
+        return run_cycles(outputs, rx).await;
+    }
+
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let mut sigint = signal(SignalKind::interrupt())?;
+    let mut sighup = signal(SignalKind::hangup())?;
+
+    let notifier = Notifier::from_env();
+    check_watchdog(&notifier, &collectors);
+    let _ = notifier.notify("READY=1\nSTATUS=Waiting for the first collection cycle");
+
+    loop {
+        tokio::select! {
+            Some(payload) = rx.recv() => {
+                let timestamp = payload["timestamp"].as_u64().unwrap_or(0);
+                outputs.send(payload).await;
+                // Only a cycle that made it through the outputs counts as progress, and only while
+                // no collector is stuck: payloads keep coming around a hung collector, but the
+                // watchdog should still fire for it.
+                if collectors.stalled() {
+                    notifier.status(&format!(
+                        "Last cycle published at {}, a collector is overrunning its deadline",
+                        timestamp
+                    ));
                 } else {
-                    println!(
-                        "Sent metrics to {} ({} bytes)",
-                        cli.destination,
-                        bytes.len()
-                    );
+                    notifier.status(&format!("Last cycle published at {}", timestamp));
+                    notifier.ping_watchdog();
                 }
             }
-            OutputMode::Stdout => {
-                println!("{}", serde_json::to_string_pretty(&combined).unwrap());
-            }
-            OutputMode::Both => {
-                // print
-                println!("{}", serde_json::to_string_pretty(&combined).unwrap());
-
-                // and send
-                if let Err(e) = socket.send_to(&bytes, cli.destination).await {
-                    eprintln!("Failed to send UDP packet: {}", e);
-                } else {
-                    println!(
-                        "Sent metrics to {} ({} bytes)",
-                        cli.destination,
-                        bytes.len()
-                    );
+            _ = sigterm.recv() => break,
+            _ = sigint.recv() => break,
+            _ = sighup.recv() => {
+                let _ = notifier.notify("RELOADING=1");
+                match reload(&mut outputs, &mut collectors, &socket, &tx, &notifier).await {
+                    Ok(()) => eprintln!("Reloaded configuration"),
+                    Err(e) => eprintln!("Failed to reload configuration, keeping the old one: {}", e),
                 }
+                let _ = notifier.notify("READY=1");
             }
         }
-        tokio::time::sleep(Duration::from_secs(cli.collection_interval)).await;
     }
+
+    // Let running cycles finish, publish what they produce, then flush buffered outputs.
+    eprintln!("Shutting down");
+    let _ = notifier.notify("STOPPING=1");
+    collectors.stop();
+    drop(tx);
+    let drained = tokio::time::timeout(collectors.shutdown_timeout, async {
+        while let Some(payload) = rx.recv().await {
+            outputs.send(payload).await;
+        }
+    })
+    .await;
+    if drained.is_err() {
+        eprintln!(
+            "Collections still running after {:?}, exiting without them",
+            collectors.shutdown_timeout
+        );
+    }
+    outputs.flush().await;
+    exit(0)
 }
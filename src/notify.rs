@@ -0,0 +1,86 @@
+// src/notify.rs
+//! systemd readiness and watchdog notifications (sd_notify protocol).
+
+use std::io;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::time::Duration;
+
+/// Sends state changes to systemd when running under a `Type=notify` unit.
+/// Every call is a no-op when `NOTIFY_SOCKET` is not set.
+pub struct Notifier {
+    target: Option<(UnixDatagram, SocketAddr)>,
+    watchdog: Option<Duration>,
+}
+
+impl Notifier {
+    /// Function to build a notifier from the environment systemd sets up for the service.
+    pub fn from_env() -> Self {
+        let var = |name| std::env::var(name).ok();
+        Self::new(
+            var("NOTIFY_SOCKET").as_deref(),
+            var("WATCHDOG_USEC").as_deref(),
+            var("WATCHDOG_PID").as_deref(),
+        )
+    }
+
+    /// Function to build a notifier from raw `NOTIFY_SOCKET`, `WATCHDOG_USEC` and `WATCHDOG_PID` values.
+    pub fn new(
+        socket: Option<&str>,
+        watchdog_usec: Option<&str>,
+        watchdog_pid: Option<&str>,
+    ) -> Self {
+        let target = socket.and_then(|socket| {
+            // A leading '@' means a socket in the abstract namespace.
+            let addr = match socket.strip_prefix('@') {
+                Some(name) => SocketAddr::from_abstract_name(name),
+                None => SocketAddr::from_pathname(socket),
+            };
+            match (UnixDatagram::unbound(), addr) {
+                (Ok(datagram), Ok(addr)) => Some((datagram, addr)),
+                (Err(e), _) | (_, Err(e)) => {
+                    eprintln!("Ignoring NOTIFY_SOCKET {}: {}", socket, e);
+                    None
+                }
+            }
+        });
+
+        let for_us = watchdog_pid.is_none_or(|pid| pid.parse() == Ok(std::process::id()));
+        let watchdog = watchdog_usec
+            .and_then(|usec| usec.parse::<u64>().ok())
+            .filter(|usec| *usec > 0 && for_us)
+            .map(Duration::from_micros);
+
+        Self { target, watchdog }
+    }
+
+    /// Function to get the watchdog timeout systemd expects pings within, if enabled.
+    pub fn watchdog(&self) -> Option<Duration> {
+        self.watchdog
+    }
+
+    /// Function to send a raw state string such as `READY=1` or `STATUS=...`.
+    pub fn notify(&self, state: &str) -> io::Result<()> {
+        if let Some((datagram, addr)) = &self.target {
+            datagram.send_to_addr(state.as_bytes(), addr)?;
+        }
+        Ok(())
+    }
+
+    /// Function to update the status line shown by `systemctl status`.
+    pub fn status(&self, status: &str) {
+        if let Err(e) = self.notify(&format!("STATUS={}", status)) {
+            eprintln!("Failed to notify systemd: {}", e);
+        }
+    }
+
+    /// Function to tell the watchdog the daemon is making progress, if it is enabled.
+    pub fn ping_watchdog(&self) {
+        if self.watchdog.is_none() {
+            return;
+        }
+        if let Err(e) = self.notify("WATCHDOG=1") {
+            eprintln!("Failed to notify systemd: {}", e);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+// src/output/elasticsearch.rs
+//! Elasticsearch/OpenSearch output: one document per record via the `_bulk` API.
+
+use crate::stats::STATS;
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderValue};
+use serde_json::{Map, Value, json};
+use std::time::Duration;
+
+/// Settings for the Elasticsearch output.
+#[derive(Debug, Clone)]
+pub struct ElasticConfig {
+    /// Base URL of the cluster (e.g. https://opensearch:9200).
+    pub url: String,
+    /// Index name prefix; documents go to `<prefix>-YYYY.MM.DD`.
+    pub index_prefix: String,
+    /// Basic auth user name.
+    pub username: Option<String>,
+    /// Basic auth password.
+    pub password: Option<String>,
+    /// Timeout for a single bulk request.
+    pub timeout: Duration,
+    /// Retries for failed requests and for items rejected with 429/5xx.
+    pub retries: u32,
+}
+
+pub struct ElasticSink {
+    client: reqwest::Client,
+    config: ElasticConfig,
+}
+
+impl ElasticSink {
+    pub fn new(config: ElasticConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-ndjson"),
+        );
+
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .default_headers(headers)
+            .build()?;
+
+        Ok(Self { client, config })
+    }
+
+    /// Function to index a payload, retrying only the documents that failed.
+    pub async fn send(&self, payload: &Value) {
+        let mut pending = flatten_payload(payload);
+        if pending.is_empty() {
+            return;
+        }
+
+        let timestamp = payload["timestamp"].as_u64().unwrap_or(0);
+        let index = index_name(&self.config.index_prefix, timestamp);
+        let total = pending.len();
+        let mut attempt = 0;
+
+        loop {
+            let retry = match self.bulk(&index, &pending).await {
+                Ok(items) => {
+                    let mut retry = Vec::new();
+                    for (doc, item) in pending.into_iter().zip(items) {
+                        let status = item["status"].as_u64().unwrap_or(0);
+                        if status == 429 || status >= 500 {
+                            retry.push(doc);
+                        } else if status >= 300 {
+                            STATS.record_dropped(1);
+                            eprintln!(
+                                "Elasticsearch rejected document for {}: {}",
+                                doc["collector"], item["error"]
+                            );
+                        }
+                    }
+                    retry
+                }
+                Err(e) => {
+                    eprintln!("Failed to send bulk request to {}: {}", self.config.url, e);
+                    STATS.record_failure();
+                    pending
+                }
+            };
+
+            if retry.is_empty() {
+                println!("Indexed {} document(s) into {}", total, index);
+                return;
+            }
+            if attempt >= self.config.retries {
+                STATS.record_dropped(retry.len());
+                eprintln!(
+                    "Dropping {} document(s) for {} after {} retries",
+                    retry.len(),
+                    index,
+                    attempt
+                );
+                return;
+            }
+
+            tokio::time::sleep(Duration::from_millis(250 << attempt.min(6))).await;
+            attempt += 1;
+            pending = retry;
+        }
+    }
+
+    /// Function to POST one bulk request and return the per-document results in order, one for
+    /// every document sent.
+    async fn bulk(&self, index: &str, docs: &[Value]) -> Result<Vec<Value>, String> {
+        let mut body = String::new();
+        for doc in docs {
+            body.push_str(&json!({ "index": { "_index": index } }).to_string());
+            body.push('\n');
+            body.push_str(&doc.to_string());
+            body.push('\n');
+        }
+
+        let url = format!("{}/_bulk", self.config.url.trim_end_matches('/'));
+        let size = body.len();
+        let mut request = self.client.post(url).body(body);
+        if let Some(user) = &self.config.username {
+            request = request.basic_auth(user, self.config.password.as_ref());
+        }
+
+        let resp = request.send().await.map_err(|e| e.to_string())?;
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(format!("bulk request returned {}", status));
+        }
+        STATS.record_sent(size, 0);
+
+        let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
+        let parsed: Value = serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+        let items: Vec<Value> = parsed["items"]
+            .as_array()
+            .ok_or("bulk response has no items")?
+            .iter()
+            .map(|item| item["index"].clone())
+            .collect();
+        // A truncated response cannot be matched to the documents, so the request counts as failed.
+        if items.len() != docs.len() {
+            return Err(format!(
+                "bulk response has {} item(s) for {} document(s)",
+                items.len(),
+                docs.len()
+            ));
+        }
+        Ok(items)
+    }
+}
+
+/// Function to turn a payload into one document per record.
+/// Arrays yield a document per element (disk mount, interface, NVMe controller),
+/// objects a single document, and loose scalars are gathered into a `sys` document.
+/// Host labels are copied into every document.
+pub fn flatten_payload(payload: &Value) -> Vec<Value> {
+    let timestamp = payload["timestamp"].as_u64().unwrap_or(0);
+    let hostname = payload["hostname"].as_str().unwrap_or("unknown");
+    let Some(metrics) = payload["metrics"].as_object() else {
+        return Vec::new();
+    };
+
+    let document = |collector: &str, fields: &Map<String, Value>| {
+        let mut doc = Map::new();
+        doc.insert("@timestamp".to_string(), json!(super::rfc3339(timestamp)));
+        doc.insert("hostname".to_string(), json!(hostname));
+        doc.insert("collector".to_string(), json!(collector));
+        if let Some(labels) = payload.get("labels") {
+            doc.insert("labels".to_string(), labels.clone());
+        }
+        for (key, value) in fields {
+            doc.insert(key.clone(), value.clone());
+        }
+        Value::Object(doc)
+    };
+
+    let mut docs = Vec::new();
+    let mut scalars = Map::new();
+    for (collector, value) in metrics {
+        match value {
+            Value::Array(records) => {
+                for record in records {
+                    if let Value::Object(fields) = record {
+                        docs.push(document(collector, fields));
+                    }
+                }
+            }
+            Value::Object(fields) => docs.push(document(collector, fields)),
+            _ if collector == "timestamp" || collector == "hostname" => {}
+            _ => {
+                scalars.insert(collector.clone(), value.clone());
+            }
+        }
+    }
+    if !scalars.is_empty() {
+        docs.push(document("sys", &scalars));
+    }
+
+    docs
+}
+
+/// Function to build the date-based index name for a timestamp.
+pub fn index_name(prefix: &str, timestamp: u64) -> String {
+    let (year, month, day) = super::civil_date(timestamp);
+    format!("{}-{:04}.{:02}.{:02}", prefix, year, month, day)
+}
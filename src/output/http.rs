@@ -0,0 +1,364 @@
+// src/output/http.rs
+//! HTTP webhook output: batches payloads and POSTs them as a JSON array.
+
+use crate::stats::STATS;
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Most spooled batches resent per flush, so a long backlog drains over several cycles instead
+/// of holding up the other outputs.
+const REPLAY_BATCHES: usize = 16;
+
+/// Settings for the HTTP output.
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    /// Endpoint batches are POSTed to.
+    pub url: String,
+    /// Extra headers sent with every request (e.g. Authorization).
+    pub headers: Vec<(String, String)>,
+    /// Number of payloads collected before a request is made.
+    pub batch_size: usize,
+    /// Timeout for a single request.
+    pub timeout: Duration,
+    /// Retries on 429 and 5xx responses and connection errors.
+    pub retries: u32,
+    /// File undeliverable batches are appended to, one JSON array per line. How much of it was
+    /// delivered is kept in `<spool>.offset`. Batches are dropped when this is unset.
+    pub spool: Option<PathBuf>,
+    /// Spool file size after which new batches are dropped.
+    pub spool_max_bytes: u64,
+}
+
+/// Why a request did not go through.
+enum PostError {
+    /// The endpoint answered with a 4xx other than 429; retrying will not help.
+    Rejected(StatusCode),
+    /// Retries were exhausted on 429 or 5xx responses or connection errors.
+    Unavailable(String),
+}
+
+pub struct HttpSink {
+    client: reqwest::Client,
+    config: HttpConfig,
+    batch: Vec<Value>,
+}
+
+impl HttpSink {
+    pub fn new(config: HttpConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        for (name, value) in &config.headers {
+            // Append so a header given several times is sent several times.
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            );
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .default_headers(headers)
+            .build()?;
+
+        Ok(Self {
+            client,
+            config,
+            batch: Vec::new(),
+        })
+    }
+
+    /// Function to queue a payload, sending the batch once it is full.
+    pub async fn push(&mut self, payload: Value) {
+        self.batch.push(payload);
+        if self.batch.len() >= self.config.batch_size.max(1) {
+            self.flush().await;
+        }
+    }
+
+    /// Function to send whatever is queued, spooling or dropping it on failure. Spooled batches
+    /// go out first so data arrives in order; while a backlog remains, new batches queue behind it.
+    pub async fn flush(&mut self) {
+        if self.batch.is_empty() {
+            return;
+        }
+
+        let batch = std::mem::take(&mut self.batch);
+        let count = batch.len();
+        let body = serde_json::to_vec(&Value::Array(batch)).unwrap();
+
+        if !self.replay_spool().await {
+            if !self.spool(body).await {
+                STATS.record_dropped(count);
+            }
+            return;
+        }
+
+        match self.post(&body, self.config.retries).await {
+            Ok(()) => {
+                STATS.record_sent(body.len(), 0);
+                println!(
+                    "Sent {} payload(s) to {} ({} bytes)",
+                    count,
+                    self.config.url,
+                    body.len()
+                );
+            }
+            Err(PostError::Rejected(status)) => {
+                eprintln!("HTTP endpoint rejected batch with {}, dropping it", status);
+                STATS.record_failure();
+                STATS.record_dropped(count);
+            }
+            Err(PostError::Unavailable(e)) => {
+                eprintln!("Failed to POST metrics to {}: {}", self.config.url, e);
+                STATS.record_failure();
+                if !self.spool(body).await {
+                    STATS.record_dropped(count);
+                }
+            }
+        }
+    }
+
+    /// Function to POST a body, retrying with backoff on 429, 5xx and connection errors.
+    async fn post(&self, body: &[u8], retries: u32) -> Result<(), PostError> {
+        let mut attempt = 0;
+        loop {
+            let err = match self
+                .client
+                .post(&self.config.url)
+                .body(body.to_vec())
+                .send()
+                .await
+            {
+                Ok(resp) if resp.status().is_success() => return Ok(()),
+                Ok(resp)
+                    if resp.status().is_server_error()
+                        || resp.status() == StatusCode::TOO_MANY_REQUESTS =>
+                {
+                    format!("server returned {}", resp.status())
+                }
+                Ok(resp) => return Err(PostError::Rejected(resp.status())),
+                Err(e) => e.to_string(),
+            };
+
+            if attempt >= retries {
+                return Err(PostError::Unavailable(err));
+            }
+            tokio::time::sleep(Duration::from_millis(250 << attempt.min(6))).await;
+            attempt += 1;
+        }
+    }
+
+    /// Function to append an undeliverable batch to the spool file, returning whether it was kept.
+    async fn spool(&self, body: Vec<u8>) -> bool {
+        let Some(path) = self.config.spool.clone() else {
+            eprintln!("No spool configured, dropping batch");
+            return false;
+        };
+
+        let max_bytes = self.config.spool_max_bytes;
+        let result = blocking({
+            let path = path.clone();
+            move || append_spool(&path, &body, max_bytes)
+        })
+        .await;
+        match result {
+            Ok(true) => true,
+            Ok(false) => {
+                eprintln!("Spool {} is full, dropping batch", path.display());
+                false
+            }
+            Err(e) => {
+                eprintln!("Failed to spool batch to {}: {}", path.display(), e);
+                false
+            }
+        }
+    }
+
+    /// Function to resend spooled batches, oldest first, returning whether the spool is drained.
+    /// At most `REPLAY_BATCHES` are sent per call, each in a single attempt, and replaying stops
+    /// once a request timeout's worth of time is spent; the rest waits for the next flush.
+    async fn replay_spool(&mut self) -> bool {
+        let Some(path) = self.config.spool.clone() else {
+            return true;
+        };
+
+        let batches = match blocking({
+            let path = path.clone();
+            move || read_spool(&path, REPLAY_BATCHES)
+        })
+        .await
+        {
+            Ok(batches) => batches,
+            Err(e) => {
+                eprintln!("Failed to read spool {}: {}", path.display(), e);
+                return true;
+            }
+        };
+        if batches.is_empty() {
+            return true;
+        }
+
+        let budget = Instant::now() + self.config.timeout;
+        let mut done = 0;
+        let mut offset = None;
+        for (line, end) in &batches {
+            if done > 0 && Instant::now() >= budget {
+                break;
+            }
+            match self.post(line.as_bytes(), 0).await {
+                Ok(()) => STATS.record_sent(line.len(), 0),
+                Err(PostError::Rejected(status)) => {
+                    eprintln!(
+                        "HTTP endpoint rejected spooled batch with {}, dropping it",
+                        status
+                    );
+                    let count = serde_json::from_str::<Value>(line)
+                        .ok()
+                        .and_then(|batch| batch.as_array().map(Vec::len))
+                        .unwrap_or(1);
+                    STATS.record_dropped(count);
+                }
+                Err(PostError::Unavailable(e)) => {
+                    eprintln!("Failed to replay spool to {}: {}", self.config.url, e);
+                    STATS.record_failure();
+                    break;
+                }
+            }
+            done += 1;
+            offset = Some(*end);
+        }
+
+        let Some(offset) = offset else {
+            return false;
+        };
+        println!("Replayed {} spooled batch(es) to {}", done, self.config.url);
+        match blocking({
+            let path = path.clone();
+            move || advance_spool(&path, offset)
+        })
+        .await
+        {
+            Ok(drained) => drained,
+            Err(e) => {
+                eprintln!("Failed to update spool {}: {}", path.display(), e);
+                false
+            }
+        }
+    }
+}
+
+/// Function to run spool file I/O on the blocking pool instead of the output task.
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> io::Result<T> + Send + 'static,
+) -> io::Result<T> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .unwrap_or_else(|e| Err(io::Error::other(e)))
+}
+
+/// Function to name the file next to the spool that records how much of it was delivered, so
+/// replaying never rewrites the spool and a restart does not resend delivered batches.
+fn offset_path(spool: &Path) -> PathBuf {
+    let mut path = spool.as_os_str().to_owned();
+    path.push(".offset");
+    PathBuf::from(path)
+}
+
+/// Function to read the delivered prefix of the spool; a missing or stale offset means none.
+fn read_offset(spool: &Path, len: u64) -> u64 {
+    fs::read_to_string(offset_path(spool))
+        .ok()
+        .and_then(|offset| offset.trim().parse().ok())
+        .filter(|&offset| offset <= len)
+        .unwrap_or(0)
+}
+
+/// Function to read up to `max` undelivered batches, each with the spool offset just past it.
+fn read_spool(spool: &Path, max: usize) -> io::Result<Vec<(String, u64)>> {
+    let file = match fs::File::open(spool) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut offset = read_offset(spool, file.metadata()?.len());
+    let mut reader = BufReader::new(file);
+    reader.seek(SeekFrom::Start(offset))?;
+
+    let mut batches = Vec::new();
+    let mut line = String::new();
+    while batches.len() < max {
+        line.clear();
+        let n = reader.read_line(&mut line)?;
+        // A line without its newline is still being appended.
+        if n == 0 || !line.ends_with('\n') {
+            break;
+        }
+        offset += n as u64;
+        let batch = line.trim_end();
+        if !batch.is_empty() {
+            batches.push((batch.to_string(), offset));
+        }
+    }
+    Ok(batches)
+}
+
+/// Function to mark the spool as delivered up to `offset`, removing it once nothing is left.
+/// Returns whether the spool is drained.
+fn advance_spool(spool: &Path, offset: u64) -> io::Result<bool> {
+    if offset < fs::metadata(spool)?.len() {
+        fs::write(offset_path(spool), offset.to_string())?;
+        return Ok(false);
+    }
+    fs::remove_file(spool)?;
+    match fs::remove_file(offset_path(spool)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(true),
+    }
+}
+
+/// Function to append a batch to the spool, returning `false` when it would exceed `max_bytes`.
+/// A spool that is full of delivered batches is compacted first.
+fn append_spool(spool: &Path, body: &[u8], max_bytes: u64) -> io::Result<bool> {
+    let needed = body.len() as u64 + 1;
+    let len = match fs::metadata(spool) {
+        Ok(meta) => meta.len(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+        Err(e) => return Err(e),
+    };
+    if len + needed > max_bytes {
+        let offset = read_offset(spool, len);
+        if offset == 0 || len - offset + needed > max_bytes {
+            return Ok(false);
+        }
+        let mut file = fs::File::open(spool)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut rest = Vec::new();
+        file.read_to_end(&mut rest)?;
+        let tmp = offset_path(spool).with_extension("tmp");
+        fs::write(&tmp, rest)?;
+        fs::rename(&tmp, spool)?;
+        fs::remove_file(offset_path(spool))?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(spool)?;
+    file.write_all(body)?;
+    file.write_all(b"\n")?;
+    Ok(true)
+}
+
+/// Function to parse a `Name: value` header given on the command line.
+pub fn parse_header(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected `Name: value`, got `{}`", s))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("missing header name in `{}`", s));
+    }
+    Ok((name.to_string(), value.trim().to_string()))
+}
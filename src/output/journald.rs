@@ -0,0 +1,115 @@
+// src/output/journald.rs
+//! systemd journal output using the native journald datagram protocol.
+
+use super::syslog::{LogFilter, LogRecord, log_records};
+use crate::stats::STATS;
+use serde_json::Value;
+use std::path::PathBuf;
+use tokio::net::UnixDatagram;
+
+/// Default location of the journald native socket.
+pub const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Function to turn a record key into a valid journal field name (e.g. used_percent -> USED_PERCENT).
+pub fn journal_field_name(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let name = name.trim_start_matches(|c: char| c == '_' || c.is_ascii_digit());
+    if name.is_empty() {
+        "FIELD".to_string()
+    } else {
+        name.chars().take(64).collect()
+    }
+}
+
+/// Function to serialize a record into a journald native protocol datagram.
+pub fn encode_journal_entry(record: &LogRecord) -> Vec<u8> {
+    let mut entry = Vec::new();
+    let mut field = |name: &str, value: &str| {
+        if value.contains('\n') {
+            // Multi-line values use the length-prefixed binary form.
+            entry.extend_from_slice(name.as_bytes());
+            entry.push(b'\n');
+            entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+            entry.extend_from_slice(value.as_bytes());
+        } else {
+            entry.extend_from_slice(name.as_bytes());
+            entry.push(b'=');
+            entry.extend_from_slice(value.as_bytes());
+        }
+        entry.push(b'\n');
+    };
+
+    field("MESSAGE", &record.message());
+    field("PRIORITY", &(record.severity as u8).to_string());
+    field("SYSLOG_IDENTIFIER", "tinycollectd");
+    field("HOSTNAME", &record.hostname);
+    field("COLLECTOR", &record.collector);
+    for (key, value) in &record.fields {
+        let value = match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        field(&journal_field_name(key), &value);
+    }
+    for (key, value) in &record.labels {
+        // The prefix counts towards journald's 64-character limit on field names.
+        field(
+            &journal_field_name(&format!("LABEL_{}", key)),
+            value.as_str().unwrap_or_default(),
+        );
+    }
+
+    entry
+}
+
+pub struct JournaldSink {
+    path: PathBuf,
+    filter: LogFilter,
+    socket: Option<UnixDatagram>,
+}
+
+impl JournaldSink {
+    pub fn new(path: PathBuf, filter: LogFilter) -> Self {
+        Self {
+            path,
+            filter,
+            socket: None,
+        }
+    }
+
+    /// Function to write every record of a payload that passes the filter to the journal.
+    pub async fn send(&mut self, payload: &Value) {
+        for record in log_records(payload, &self.filter) {
+            let entry = encode_journal_entry(&record);
+            match self.write(&entry).await {
+                Ok(()) => STATS.record_sent(entry.len(), 1),
+                Err(e) => {
+                    eprintln!("Failed to write to journal: {}", e);
+                    STATS.record_failure();
+                }
+            }
+        }
+    }
+
+    async fn write(&mut self, entry: &[u8]) -> std::io::Result<()> {
+        if self.socket.is_none() {
+            let socket = UnixDatagram::unbound()?;
+            socket.connect(&self.path)?;
+            self.socket = Some(socket);
+        }
+        if let Err(e) = self.socket.as_ref().unwrap().send(entry).await {
+            self.socket = None;
+            return Err(e);
+        }
+        Ok(())
+    }
+}
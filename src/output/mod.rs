@@ -0,0 +1,79 @@
+// src/output/mod.rs
+
+pub mod elasticsearch;
+pub mod http;
+pub mod journald;
+pub mod syslog;
+
+pub use elasticsearch::{ElasticConfig, ElasticSink, flatten_payload, index_name};
+pub use http::{HttpConfig, HttpSink, parse_header};
+pub use journald::{JOURNAL_SOCKET, JournaldSink, encode_journal_entry, journal_field_name};
+use serde_json::{Map, Value};
+
+pub use syslog::{
+    LogFilter, LogRecord, Severity, SyslogSink, SyslogTransport, format_rfc5424, log_records,
+    parse_syslog_address,
+};
+
+/// Function to parse a host label such as `dc=ams1`.
+/// Names are restricted to letters, digits and `_` so every output can carry them unchanged.
+pub fn parse_label(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected <name>=<value>, got `{}`", s))?;
+    let name = name.trim();
+    if name.is_empty()
+        || name.starts_with(|c: char| c.is_ascii_digit())
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(format!(
+            "label name `{}` must be letters, digits and _ and not start with a digit",
+            name
+        ));
+    }
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+/// Function to attach host labels to a payload as a top-level `labels` object.
+pub fn apply_labels(payload: &mut Value, labels: &[(String, String)]) {
+    if labels.is_empty() {
+        return;
+    }
+    let labels: Map<String, Value> = labels
+        .iter()
+        .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+        .collect();
+    if let Value::Object(payload) = payload {
+        payload.insert("labels".to_string(), Value::Object(labels));
+    }
+}
+
+/// Function to convert epoch seconds into a UTC (year, month, day).
+pub fn civil_date(timestamp: u64) -> (i64, u32, u32) {
+    // Days-to-civil conversion from Howard Hinnant's date algorithms.
+    let z = (timestamp / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Function to format epoch seconds as an RFC 3339 UTC timestamp.
+pub fn rfc3339(timestamp: u64) -> String {
+    let (year, month, day) = civil_date(timestamp);
+    let secs = timestamp % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
@@ -0,0 +1,287 @@
+// src/output/syslog.rs
+//! Syslog output: one RFC 5424 message per record over UDP, TCP or a Unix socket.
+
+use crate::stats::STATS;
+use serde_json::{Map, Value};
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket, UnixDatagram};
+
+/// Facility used for every message (daemon).
+const FACILITY: u8 = 3;
+
+/// How long connecting to or writing to the syslog server may take before the message is
+/// given up on, so a stuck server does not hold up the other outputs.
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Syslog severities tinycollectd emits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Crit = 2,
+    Warning = 4,
+    Info = 6,
+}
+
+/// Which records become log messages and at what severity.
+#[derive(Debug, Clone)]
+pub struct LogFilter {
+    /// Disk used_percent at or above which a mount is logged as a warning.
+    pub disk_warn_percent: f64,
+    /// Only log records that are above info severity.
+    pub problems_only: bool,
+}
+
+/// A single record ready to be written to a log.
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub timestamp: u64,
+    pub hostname: String,
+    pub collector: String,
+    pub severity: Severity,
+    pub fields: Map<String, Value>,
+    /// Host labels, kept apart from the record's own fields.
+    pub labels: Map<String, Value>,
+}
+
+impl LogRecord {
+    /// Function to render the record as `collector key=value ...`.
+    pub fn message(&self) -> String {
+        let mut msg = self.collector.clone();
+        for (key, value) in &self.fields {
+            match value {
+                Value::String(s) => msg.push_str(&format!(" {}={}", key, s)),
+                other => msg.push_str(&format!(" {}={}", key, other)),
+            }
+        }
+        msg
+    }
+}
+
+/// Function to split a payload into log records, applying the filter.
+pub fn log_records(payload: &Value, filter: &LogFilter) -> Vec<LogRecord> {
+    super::flatten_payload(payload)
+        .into_iter()
+        .filter_map(|doc| {
+            let Value::Object(mut fields) = doc else {
+                return None;
+            };
+            fields.remove("@timestamp");
+            let hostname = fields.remove("hostname")?.as_str()?.to_string();
+            let collector = fields.remove("collector")?.as_str()?.to_string();
+            let labels = match fields.remove("labels") {
+                Some(Value::Object(labels)) => labels,
+                _ => Map::new(),
+            };
+
+            let severity = match collector.as_str() {
+                "smart_log" if fields["critical_warning"].as_u64().unwrap_or(0) != 0 => {
+                    Severity::Crit
+                }
+                "disk_usage"
+                    if fields["used_percent"].as_f64().unwrap_or(0.0)
+                        >= filter.disk_warn_percent =>
+                {
+                    Severity::Warning
+                }
+                "alerts" if fields["state"] == "firing" => Severity::Crit,
+                // A resolved alert is still a state change worth keeping with --log-problems-only.
+                "alerts" => Severity::Warning,
+                _ => Severity::Info,
+            };
+            if filter.problems_only && severity == Severity::Info {
+                return None;
+            }
+
+            Some(LogRecord {
+                timestamp: payload["timestamp"].as_u64().unwrap_or(0),
+                hostname,
+                collector,
+                severity,
+                fields,
+                labels,
+            })
+        })
+        .collect()
+}
+
+/// Function to format a record as an RFC 5424 message. Structured data needs an SD-ID of the
+/// form `name@<private enterprise number>`; without an enterprise number the fields and labels
+/// are only carried in the message text.
+pub fn format_rfc5424(record: &LogRecord, enterprise: Option<u32>) -> String {
+    let pri = FACILITY * 8 + record.severity as u8;
+    let hostname: String = record
+        .hostname
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(255)
+        .collect();
+
+    let mut message = record.message();
+    let sd = match enterprise {
+        Some(enterprise) => {
+            let mut sd = format!("[tinycollectd@{}", enterprise);
+            push_sd_params(&mut sd, &record.fields);
+            sd.push(']');
+            if !record.labels.is_empty() {
+                sd.push_str(&format!("[labels@{}", enterprise));
+                push_sd_params(&mut sd, &record.labels);
+                sd.push(']');
+            }
+            sd
+        }
+        None => {
+            for (key, value) in &record.labels {
+                match value {
+                    Value::String(s) => message.push_str(&format!(" label.{}={}", key, s)),
+                    other => message.push_str(&format!(" label.{}={}", key, other)),
+                }
+            }
+            "-".to_string()
+        }
+    };
+
+    format!(
+        "<{}>1 {} {} tinycollectd {} {} {} {}",
+        pri,
+        super::rfc3339(record.timestamp),
+        if hostname.is_empty() { "-" } else { &hostname },
+        std::process::id(),
+        record.collector,
+        sd,
+        message
+    )
+}
+
+/// Function to append `name="value"` structured data parameters, escaped as RFC 5424 requires.
+fn push_sd_params(sd: &mut String, params: &Map<String, Value>) {
+    for (key, value) in params {
+        let name: String = key
+            .chars()
+            .filter(|c| c.is_ascii_graphic() && !matches!(c, '=' | ']' | '"' | ' '))
+            .take(32)
+            .collect();
+        let raw = match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        let escaped = raw
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace(']', "\\]");
+        sd.push_str(&format!(" {}=\"{}\"", name, escaped));
+    }
+}
+
+/// Where syslog messages are sent.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyslogTransport {
+    Udp(String),
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+/// Function to parse `udp://host:port`, `tcp://host:port` or `unix:///dev/log`.
+pub fn parse_syslog_address(s: &str) -> Result<SyslogTransport, String> {
+    match s.split_once("://") {
+        Some(("udp", addr)) => Ok(SyslogTransport::Udp(addr.to_string())),
+        Some(("tcp", addr)) => Ok(SyslogTransport::Tcp(addr.to_string())),
+        Some(("unix", path)) => Ok(SyslogTransport::Unix(PathBuf::from(path))),
+        _ => Err(format!(
+            "expected udp://host:port, tcp://host:port or unix:///path, got `{}`",
+            s
+        )),
+    }
+}
+
+pub struct SyslogSink {
+    transport: SyslogTransport,
+    filter: LogFilter,
+    /// Private enterprise number used in structured data IDs.
+    enterprise: Option<u32>,
+    udp: Option<UdpSocket>,
+    tcp: Option<TcpStream>,
+    unix: Option<UnixDatagram>,
+}
+
+impl SyslogSink {
+    pub fn new(transport: SyslogTransport, filter: LogFilter, enterprise: Option<u32>) -> Self {
+        Self {
+            transport,
+            filter,
+            enterprise,
+            udp: None,
+            tcp: None,
+            unix: None,
+        }
+    }
+
+    /// Function to log every record of a payload that passes the filter. The rest of the
+    /// payload is skipped after a failure, so an unreachable server costs one timeout per cycle.
+    pub async fn send(&mut self, payload: &Value) {
+        for record in log_records(payload, &self.filter) {
+            let line = format_rfc5424(&record, self.enterprise);
+            let result = match tokio::time::timeout(SEND_TIMEOUT, self.write(&line)).await {
+                Ok(result) => result,
+                Err(_) => {
+                    // A partially written TCP frame would corrupt the stream.
+                    self.tcp = None;
+                    Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        format!("no progress within {:?}", SEND_TIMEOUT),
+                    ))
+                }
+            };
+            match result {
+                Ok(()) => {
+                    let datagrams = u64::from(!matches!(self.transport, SyslogTransport::Tcp(_)));
+                    STATS.record_sent(line.len(), datagrams);
+                }
+                Err(e) => {
+                    eprintln!("Failed to send syslog message: {}", e);
+                    STATS.record_failure();
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Function to write one message, (re)connecting lazily.
+    async fn write(&mut self, line: &str) -> io::Result<()> {
+        match &self.transport {
+            SyslogTransport::Udp(addr) => {
+                if self.udp.is_none() {
+                    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+                    socket.connect(addr).await?;
+                    self.udp = Some(socket);
+                }
+                self.udp.as_ref().unwrap().send(line.as_bytes()).await?;
+            }
+            SyslogTransport::Tcp(addr) => {
+                if self.tcp.is_none() {
+                    self.tcp = Some(TcpStream::connect(addr).await?);
+                }
+                // Octet-counting framing from RFC 6587.
+                let framed = format!("{} {}", line.len(), line);
+                let stream = self.tcp.as_mut().unwrap();
+                if let Err(e) = stream.write_all(framed.as_bytes()).await {
+                    self.tcp = None;
+                    return Err(e);
+                }
+            }
+            SyslogTransport::Unix(path) => {
+                if self.unix.is_none() {
+                    let socket = UnixDatagram::unbound()?;
+                    socket.connect(path)?;
+                    self.unix = Some(socket);
+                }
+                if let Err(e) = self.unix.as_ref().unwrap().send(line.as_bytes()).await {
+                    self.unix = None;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+}
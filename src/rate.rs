@@ -0,0 +1,106 @@
+// src/rate.rs
+//! Derives per-second rates from cumulative counters (interface bytes, NVMe data units and
+//! commands) by keeping the previous sample of every counter between cycles.
+
+use crate::collector::instance_of;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+/// Counters a rate is derived for, as (collector, counter, rate field, factor). The factor turns
+/// the counter's unit into the rate's, e.g. NVMe data units are thousands of 512-byte blocks.
+pub const COUNTERS: &[(&str, &str, &str, f64)] = &[
+    ("network", "rx_bytes", "rx_bytes_per_sec", 1.0),
+    ("network", "tx_bytes", "tx_bytes_per_sec", 1.0),
+    (
+        "smart_log",
+        "data_units_read",
+        "read_bytes_per_sec",
+        512_000.0,
+    ),
+    (
+        "smart_log",
+        "data_units_written",
+        "written_bytes_per_sec",
+        512_000.0,
+    ),
+    (
+        "smart_log",
+        "data_units_written",
+        "written_bytes_per_day",
+        512_000.0 * 86_400.0,
+    ),
+    ("smart_log", "host_read_commands", "read_iops", 1.0),
+    ("smart_log", "host_write_commands", "write_iops", 1.0),
+];
+
+/// Function to compute how much a counter grew, allowing for it wrapping at 32 or 64 bits.
+/// Any other decrease is a reset (interface recreated, device replaced) and yields `None`.
+pub fn counter_delta(previous: u64, current: u64) -> Option<u64> {
+    if current >= previous {
+        return Some(current - previous);
+    }
+    let wrapped_at = |max: u64| {
+        // Only trust a wrap when the counter was in the top quarter of its range.
+        (previous <= max && previous > max - max / 4)
+            .then(|| (max - previous).checked_add(current)?.checked_add(1))
+            .flatten()
+    };
+    wrapped_at(u32::MAX as u64).or_else(|| wrapped_at(u64::MAX))
+}
+
+/// Previous samples of every counter, keyed by (collector, instance, rate field).
+#[derive(Debug, Default)]
+pub struct Rates {
+    previous: HashMap<(String, String, String), (u64, u64)>,
+}
+
+impl Rates {
+    /// Function to add rate fields next to the counters of a payload. The first sample of a
+    /// counter, and the first after a reset, has no rate. Instances that disappear are forgotten.
+    pub fn derive(&mut self, payload: &mut Value) {
+        let timestamp = payload["timestamp"].as_u64().unwrap_or(0);
+        let Some(metrics) = payload.get_mut("metrics").and_then(Value::as_object_mut) else {
+            return;
+        };
+
+        for (collector, records) in metrics.iter_mut() {
+            let Value::Array(records) = records else {
+                continue;
+            };
+            let counters: Vec<_> = COUNTERS.iter().filter(|c| c.0 == collector).collect();
+            if counters.is_empty() {
+                continue;
+            }
+
+            let mut seen = Vec::new();
+            for record in records.iter_mut().filter_map(Value::as_object_mut) {
+                let instance = instance_of(record);
+                for (_, counter, rate_field, factor) in &counters {
+                    let Some(current) = record.get(*counter).and_then(Value::as_u64) else {
+                        continue;
+                    };
+                    let key = (collector.clone(), instance.clone(), rate_field.to_string());
+                    seen.push(key.clone());
+                    let previous = self.previous.get(&key).copied();
+                    if previous.is_some_and(|(then, _)| then == timestamp) {
+                        // Keep the older sample so the next cycle measures a real interval.
+                        continue;
+                    }
+                    self.previous.insert(key, (timestamp, current));
+
+                    let Some((then, before)) = previous.filter(|(then, _)| *then < timestamp)
+                    else {
+                        continue;
+                    };
+                    if let Some(delta) = counter_delta(before, current) {
+                        let rate = delta as f64 * factor / (timestamp - then) as f64;
+                        record.insert(rate_field.to_string(), json!(rate));
+                    }
+                }
+            }
+
+            self.previous
+                .retain(|key, _| key.0 != *collector || seen.contains(key));
+        }
+    }
+}
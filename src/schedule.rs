@@ -0,0 +1,351 @@
+// src/schedule.rs
+//! Collector scheduling: collectors sharing an interval form a group that runs on its own task.
+//! Groups tick on wall-clock boundaries (e.g. :00, :10, :20) so samples line up across hosts.
+
+use crate::collector::{
+    self, CollectorConfig, CollectorError, CollectorState, ErrorKind, MetricType, collect_metric,
+};
+use crate::stats::STATS;
+use clap::ValueEnum;
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use sysinfo::System;
+use tokio::sync::{mpsc, watch};
+use tokio::time::{Instant, MissedTickBehavior};
+
+/// Collectors that are collected and published together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub interval: Duration,
+    pub metrics: Vec<MetricType>,
+}
+
+/// What to do with ticks missed because a collection overran its interval.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum MissedTick {
+    /// Collect back to back until caught up.
+    Burst,
+    /// Restart the schedule from the late tick.
+    Delay,
+    /// Drop missed ticks and wait for the next boundary.
+    Skip,
+}
+
+impl From<MissedTick> for MissedTickBehavior {
+    fn from(missed: MissedTick) -> Self {
+        match missed {
+            MissedTick::Burst => MissedTickBehavior::Burst,
+            MissedTick::Delay => MissedTickBehavior::Delay,
+            MissedTick::Skip => MissedTickBehavior::Skip,
+        }
+    }
+}
+
+/// Settings shared by every group's ticker.
+#[derive(Debug, Clone)]
+pub struct Timing {
+    pub missed_tick: MissedTick,
+    /// Fixed offset added to every boundary so hosts do not all send at once.
+    pub splay: Duration,
+    /// Per-collector deadlines; collectors without one get their group's interval.
+    pub timeouts: Vec<(MetricType, Duration)>,
+    /// Number of cycles each group runs before returning, starting right away instead of on a
+    /// boundary; `None` runs until stopped.
+    pub cycles: Option<u64>,
+}
+
+impl Timing {
+    /// Function to look up how long a collector may run before its cycle is published without it.
+    pub fn timeout_for(&self, metric: &MetricType, interval: Duration) -> Duration {
+        self.timeouts
+            .iter()
+            .rev()
+            .find(|(m, _)| m == metric)
+            .map_or(interval, |(_, timeout)| *timeout)
+    }
+}
+
+/// Per-collector state kept between cycles.
+struct Slot {
+    metric: MetricType,
+    /// Set while a collection runs, so a hung collector is not piled up on every tick.
+    busy: Arc<AtomicBool>,
+    sys: Arc<Mutex<System>>,
+    /// Samples the collector compares against on its next cycle (e.g. cpu times).
+    state: Arc<Mutex<CollectorState>>,
+}
+
+/// Clears a slot's busy flag once its blocking collection returns, even on panic.
+struct BusyGuard(Arc<AtomicBool>);
+
+impl Drop for BusyGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Keeps a group counted in a shared tally of stalled groups while its collectors overrun,
+/// and takes it out again when the group exits.
+struct StallGuard {
+    stalled: Arc<AtomicUsize>,
+    counted: bool,
+}
+
+impl StallGuard {
+    /// Function to record whether the group's last cycle had a collector overrun.
+    fn set(&mut self, overran: bool) {
+        if overran && !self.counted {
+            self.stalled.fetch_add(1, Ordering::SeqCst);
+        } else if !overran && self.counted {
+            self.stalled.fetch_sub(1, Ordering::SeqCst);
+        }
+        self.counted = overran;
+    }
+}
+
+impl Drop for StallGuard {
+    fn drop(&mut self) {
+        self.set(false);
+    }
+}
+
+/// Random value behind the splay, drawn once so reloads keep the same offset.
+static SPLAY_SEED: OnceLock<u64> = OnceLock::new();
+
+/// Function to pick a random offset below `max`. The offset is fixed for the lifetime of the
+/// process, across configuration reloads, as long as `max` stays the same.
+pub fn random_splay(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+    // RandomState is seeded randomly per process, which is all the randomness we need.
+    let random = *SPLAY_SEED.get_or_init(|| RandomState::new().hash_one(collector::get_hostname()));
+    Duration::from_nanos(random % max.as_nanos().min(u64::MAX as u128) as u64)
+}
+
+/// Function to compute how long to wait from `now` (since the epoch) until the next
+/// multiple of `interval`, shifted by `offset`.
+pub fn delay_to_boundary(now: Duration, interval: Duration, offset: Duration) -> Duration {
+    let period = interval.as_nanos().max(1);
+    let offset = offset.as_nanos() % period;
+    let into_period = (now.as_nanos() + period - offset) % period;
+    if into_period == 0 {
+        Duration::ZERO
+    } else {
+        Duration::from_nanos((period - into_period) as u64)
+    }
+}
+
+/// Function to parse a per-collector setting such as `smart-log=300`.
+pub fn parse_interval(s: &str) -> Result<(MetricType, u64), String> {
+    let (name, secs) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected <metric>=<seconds>, got `{}`", s))?;
+    let metric = MetricType::from_str(name.trim(), true)?;
+    let secs = secs
+        .trim()
+        .parse::<u64>()
+        .map_err(|e| format!("invalid number of seconds `{}`: {}", secs, e))?;
+    Ok((metric, secs))
+}
+
+/// Function to group the selected collectors by their collection interval.
+/// `All` is expanded into its individual collectors as soon as one of them has its own interval.
+pub fn plan(
+    metrics: &[MetricType],
+    default_secs: u64,
+    overrides: &[(MetricType, u64)],
+) -> Result<Vec<Group>, String> {
+    let expand =
+        metrics.contains(&MetricType::All) && overrides.iter().any(|(m, _)| *m != MetricType::All);
+
+    let mut selected: Vec<MetricType> = Vec::new();
+    for metric in metrics {
+        let parts = if *metric == MetricType::All && expand {
+            MetricType::expand_all()
+        } else {
+            vec![metric.clone()]
+        };
+        for part in parts {
+            if !selected.contains(&part) {
+                selected.push(part);
+            }
+        }
+    }
+
+    for (metric, _) in overrides {
+        if !selected.contains(metric) {
+            return Err(format!(
+                "interval given for {:?}, which is not being collected",
+                metric
+            ));
+        }
+    }
+
+    let mut groups: BTreeMap<u64, Vec<MetricType>> = BTreeMap::new();
+    for metric in selected {
+        let secs = overrides
+            .iter()
+            .rev()
+            .find(|(m, _)| *m == metric)
+            .map_or(default_secs, |(_, secs)| *secs);
+        if secs == 0 {
+            return Err(format!(
+                "interval for {:?} must be at least 1 second",
+                metric
+            ));
+        }
+        groups.entry(secs).or_default().push(metric);
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|(secs, metrics)| Group {
+            interval: Duration::from_secs(secs),
+            metrics,
+        })
+        .collect())
+}
+
+/// Function to run every collector of a group concurrently on the blocking pool and wait for
+/// each up to its deadline. Collectors that miss it, or are still running from an earlier cycle,
+/// are reported in the returned errors and make the returned flag true.
+async fn collect_group(
+    slots: &[Slot],
+    interval: Duration,
+    timing: &Timing,
+    config: &Arc<CollectorConfig>,
+) -> (Map<String, Value>, Vec<CollectorError>, bool) {
+    let mut metrics = Map::new();
+    let mut errors = Vec::new();
+    let mut overran = false;
+    let started = Instant::now();
+
+    let mut running = Vec::new();
+    for slot in slots {
+        if slot.busy.swap(true, Ordering::SeqCst) {
+            overran = true;
+            STATS.record_dropped(1);
+            errors.push(CollectorError::new(
+                slot.metric.key(),
+                ErrorKind::Timeout,
+                "previous collection is still running",
+            ));
+            continue;
+        }
+
+        let guard = BusyGuard(slot.busy.clone());
+        let metric = slot.metric.clone();
+        let sys = slot.sys.clone();
+        let state = slot.state.clone();
+        let config = config.clone();
+        let handle = tokio::task::spawn_blocking(move || {
+            let _guard = guard;
+            let mut sys = sys.lock().unwrap_or_else(PoisonError::into_inner);
+            let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+            let began = std::time::Instant::now();
+            let collected = collect_metric(&metric, &mut sys, &config, &mut state);
+            STATS.record_duration(metric.key(), began.elapsed());
+            collected
+        });
+        running.push((slot, timing.timeout_for(&slot.metric, interval), handle));
+    }
+
+    for (slot, timeout, handle) in running {
+        match tokio::time::timeout_at(started + timeout, handle).await {
+            Ok(Ok((entries, collector_errors))) => {
+                metrics.extend(entries);
+                errors.extend(collector_errors);
+            }
+            Ok(Err(e)) => {
+                STATS.record_dropped(1);
+                errors.push(CollectorError::new(slot.metric.key(), ErrorKind::Panic, e));
+            }
+            Err(_) => {
+                overran = true;
+                STATS.record_dropped(1);
+                errors.push(CollectorError::new(
+                    slot.metric.key(),
+                    ErrorKind::Timeout,
+                    format!("did not finish within {:?}", timeout),
+                ));
+            }
+        }
+    }
+
+    (metrics, errors, overran)
+}
+
+/// Function to collect a group until `stop` flips to true or its cycles are used up, handing each
+/// payload to the outputs as it is produced. A cycle that has started is always finished and published.
+/// While the group's last cycle had a collector overrun, it is counted in `stalled`.
+pub async fn run_group(
+    group: Group,
+    timing: Timing,
+    config: Arc<CollectorConfig>,
+    tx: mpsc::Sender<Value>,
+    mut stop: watch::Receiver<bool>,
+    stalled: Arc<AtomicUsize>,
+) {
+    let mut stall = StallGuard {
+        stalled,
+        counted: false,
+    };
+    let slots: Vec<Slot> = group
+        .metrics
+        .iter()
+        .map(|metric| Slot {
+            metric: metric.clone(),
+            busy: Arc::new(AtomicBool::new(false)),
+            sys: Arc::new(Mutex::new(if metric.needs_system() {
+                System::new_all()
+            } else {
+                System::new()
+            })),
+            state: Arc::default(),
+        })
+        .collect();
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let start = match timing.cycles {
+        Some(_) => Instant::now(),
+        None => Instant::now() + delay_to_boundary(now, group.interval, timing.splay),
+    };
+    let mut ticker = tokio::time::interval_at(start, group.interval);
+    ticker.set_missed_tick_behavior(timing.missed_tick.into());
+
+    let mut remaining = timing.cycles;
+    loop {
+        if remaining == Some(0) {
+            return;
+        }
+        remaining = remaining.map(|n| n - 1);
+
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = stop.wait_for(|stopped| *stopped) => return,
+        }
+
+        let timestamp = collector::get_timestamp();
+        let (metrics, errors, overran) =
+            collect_group(&slots, group.interval, &timing, &config).await;
+        stall.set(overran);
+        let payload = json!({
+            "timestamp": timestamp,
+            "hostname": collector::get_hostname(),
+            "metrics": metrics,
+            "errors": errors,
+        });
+
+        if tx.send(payload).await.is_err() {
+            return;
+        }
+    }
+}
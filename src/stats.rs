@@ -0,0 +1,83 @@
+// src/stats.rs
+//! Counters tinycollectd keeps about itself, reported by the `self` collector.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Process-wide agent statistics.
+pub struct Stats {
+    bytes_sent: AtomicU64,
+    datagrams_sent: AtomicU64,
+    send_failures: AtomicU64,
+    dropped_samples: AtomicU64,
+    /// Last collection duration per collector key.
+    durations: Mutex<BTreeMap<String, Duration>>,
+}
+
+/// The statistics of this process.
+pub static STATS: Stats = Stats::new();
+
+impl Stats {
+    const fn new() -> Self {
+        Self {
+            bytes_sent: AtomicU64::new(0),
+            datagrams_sent: AtomicU64::new(0),
+            send_failures: AtomicU64::new(0),
+            dropped_samples: AtomicU64::new(0),
+            durations: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Function to count a successful send of `bytes`, made of `datagrams` datagrams
+    /// (0 for stream and HTTP outputs).
+    pub fn record_sent(&self, bytes: usize, datagrams: u64) {
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.datagrams_sent.fetch_add(datagrams, Ordering::Relaxed);
+    }
+
+    /// Function to count a failed send.
+    pub fn record_failure(&self) {
+        self.send_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Function to count samples that were given up on: collector results lost to a deadline,
+    /// a panic or a still-running previous collection, payloads that failed to send over UDP,
+    /// and HTTP batches or Elasticsearch documents that could not be delivered.
+    pub fn record_dropped(&self, samples: usize) {
+        self.dropped_samples
+            .fetch_add(samples as u64, Ordering::Relaxed);
+    }
+
+    /// Function to remember how long a collector took.
+    pub fn record_duration(&self, collector: &str, duration: Duration) {
+        if let Ok(mut durations) = self.durations.lock() {
+            durations.insert(collector.to_string(), duration);
+        }
+    }
+
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    pub fn datagrams_sent(&self) -> u64 {
+        self.datagrams_sent.load(Ordering::Relaxed)
+    }
+
+    pub fn send_failures(&self) -> u64 {
+        self.send_failures.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped_samples(&self) -> u64 {
+        self.dropped_samples.load(Ordering::Relaxed)
+    }
+
+    /// Function to snapshot the last duration of every collector.
+    pub fn durations(&self) -> BTreeMap<String, Duration> {
+        self.durations
+            .lock()
+            .map(|durations| durations.clone())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,272 @@
+// src/store.rs
+//! Local on-disk history of published payloads, bounded in size, plus the helpers behind
+//! `tinycollectd query`. Payloads are appended as NDJSON to `current.ndjson`; full segments
+//! are gzip-compressed into `<first timestamp>.ndjson.gz` and the oldest ones are removed
+//! once the directory outgrows its limit.
+
+use flate2::Compression;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use serde_json::Value;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+const CURRENT: &str = "current.ndjson";
+const SEGMENT_SUFFIX: &str = ".ndjson.gz";
+
+/// Bounded store of payloads in a directory.
+#[derive(Debug, Clone)]
+pub struct Store {
+    dir: PathBuf,
+    max_bytes: u64,
+    /// Size at which the uncompressed current segment is sealed.
+    segment_bytes: u64,
+}
+
+impl Store {
+    /// Function to open (creating if needed) a store directory limited to `max_bytes` on disk.
+    pub fn open(dir: &Path, max_bytes: u64) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            max_bytes,
+            segment_bytes: (max_bytes / 8).max(4096),
+        })
+    }
+
+    /// Function to open an existing store for reading, failing if the directory is missing
+    /// instead of creating it.
+    pub fn open_existing(dir: &Path) -> io::Result<Self> {
+        if !dir.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no store at {}", dir.display()),
+            ));
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            max_bytes: u64::MAX,
+            segment_bytes: u64::MAX,
+        })
+    }
+
+    /// Function to append a payload, sealing the current segment once it is full.
+    pub fn append(&self, payload: &Value) -> io::Result<()> {
+        let current = self.dir.join(CURRENT);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&current)?;
+        file.write_all(payload.to_string().as_bytes())?;
+        file.write_all(b"\n")?;
+
+        if file.metadata()?.len() >= self.segment_bytes {
+            self.seal()?;
+        }
+        Ok(())
+    }
+
+    /// Function to compress the current segment and drop the oldest segments over the limit.
+    fn seal(&self) -> io::Result<()> {
+        let current = self.dir.join(CURRENT);
+        let first = BufReader::new(File::open(&current)?)
+            .lines()
+            .map_while(Result::ok)
+            .find_map(|line| serde_json::from_str::<Value>(&line).ok())
+            .and_then(|payload| payload["timestamp"].as_u64())
+            .unwrap_or(0);
+
+        // Zero-padded so segments sort by name in time order.
+        let sealed = self.dir.join(format!("{:020}{}", first, SEGMENT_SUFFIX));
+        let partial = sealed.with_extension("tmp");
+        let mut encoder = GzEncoder::new(File::create(&partial)?, Compression::default());
+        io::copy(&mut File::open(&current)?, &mut encoder)?;
+        encoder.finish()?.sync_all()?;
+        fs::rename(&partial, &sealed)?;
+        fs::remove_file(&current)?;
+
+        self.prune()
+    }
+
+    /// Function to list sealed segments, oldest first.
+    fn segments(&self) -> io::Result<Vec<PathBuf>> {
+        let mut segments: Vec<PathBuf> = fs::read_dir(&self.dir)?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.to_string_lossy().ends_with(SEGMENT_SUFFIX))
+            .collect();
+        segments.sort();
+        Ok(segments)
+    }
+
+    /// Function to remove the oldest segments until they leave room for a full current segment
+    /// within the limit.
+    fn prune(&self) -> io::Result<()> {
+        let size = |path: &Path| fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        let segments = self.segments()?;
+        let mut total: u64 = segments.iter().map(|p| size(p)).sum::<u64>() + self.segment_bytes;
+        for segment in segments {
+            if total <= self.max_bytes {
+                break;
+            }
+            total -= size(&segment);
+            fs::remove_file(&segment)?;
+        }
+        Ok(())
+    }
+
+    /// Function to read every stored payload with a timestamp at or after `since`, oldest first.
+    pub fn read_since(&self, since: u64) -> io::Result<Vec<Value>> {
+        let segments = self.segments()?;
+        let start_of = |path: &Path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(SEGMENT_SUFFIX))
+                .and_then(|ts| ts.parse::<u64>().ok())
+                .unwrap_or(0)
+        };
+
+        let mut payloads = Vec::new();
+        for (i, segment) in segments.iter().enumerate() {
+            // A segment ends where the next one starts; skip those entirely before `since`.
+            if segments
+                .get(i + 1)
+                .is_some_and(|next| start_of(next) <= since)
+            {
+                continue;
+            }
+            read_lines(
+                BufReader::new(MultiGzDecoder::new(File::open(segment)?)),
+                since,
+                &mut payloads,
+            );
+        }
+        match File::open(self.dir.join(CURRENT)) {
+            Ok(file) => read_lines(BufReader::new(file), since, &mut payloads),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(payloads)
+    }
+}
+
+/// Function to parse NDJSON payloads newer than `since`, skipping damaged lines.
+fn read_lines(reader: impl BufRead, since: u64, out: &mut Vec<Value>) {
+    out.extend(
+        reader
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<Value>(&line).ok())
+            .filter(|payload| payload["timestamp"].as_u64().is_some_and(|ts| ts >= since)),
+    );
+}
+
+/// Function to parse a duration such as `90s`, `30m`, `2h`, `7d` or plain seconds.
+pub fn parse_duration(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (number, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("expected a duration like 90s, 30m, 2h or 7d, got `{}`", s))?;
+    let factor = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        _ => return Err(format!("unknown duration unit `{}`", unit)),
+    };
+    number
+        .checked_mul(factor)
+        .ok_or_else(|| format!("duration `{}` is too large", s))
+}
+
+/// One value of a queried metric.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub timestamp: u64,
+    pub instance: String,
+    pub value: f64,
+}
+
+/// Function to pull a metric such as `disk.used_percent` out of stored payloads.
+/// The collector part matches a payload key exactly or as a prefix (`disk` for `disk_usage`).
+pub fn select(payloads: &[Value], metric: &str) -> Vec<Sample> {
+    let (collector, field) = metric.split_once('.').unwrap_or((metric, ""));
+    let collector = collector.replace('-', "_");
+    let number = |v: &Value| {
+        v.as_f64()
+            .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+    };
+
+    let mut samples = Vec::new();
+    for payload in payloads {
+        let timestamp = payload["timestamp"].as_u64().unwrap_or(0);
+        let Some(metrics) = payload["metrics"].as_object() else {
+            continue;
+        };
+        let key = if metrics.contains_key(&collector) {
+            Some(collector.as_str())
+        } else {
+            metrics
+                .keys()
+                .map(String::as_str)
+                .find(|k| k.starts_with(&collector))
+        };
+        let Some(key) = key else {
+            continue;
+        };
+
+        let mut push = |instance: String, value: Option<f64>| {
+            if let Some(value) = value {
+                samples.push(Sample {
+                    timestamp,
+                    instance,
+                    value,
+                });
+            }
+        };
+        match &metrics[key] {
+            Value::Array(records) => {
+                for record in records.iter().filter_map(Value::as_object) {
+                    push(
+                        crate::collector::instance_of(record),
+                        record.get(field).and_then(number),
+                    );
+                }
+            }
+            Value::Object(record) => push(String::new(), record.get(field).and_then(number)),
+            scalar if field.is_empty() => push(String::new(), number(scalar)),
+            _ => {}
+        }
+    }
+    samples
+}
+
+/// Function to draw values as a sparkline at most `width` characters wide.
+pub fn sparkline(values: &[f64], width: usize) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    if values.is_empty() || width == 0 {
+        return String::new();
+    }
+
+    // Average neighbouring values when there are more than fit.
+    let chunk = values.len().div_ceil(width);
+    let points: Vec<f64> = values
+        .chunks(chunk)
+        .map(|c| c.iter().sum::<f64>() / c.len() as f64)
+        .collect();
+    let min = points.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = points.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    points
+        .iter()
+        .map(|v| {
+            if max > min {
+                BARS[(((v - min) / (max - min)) * 7.0).round() as usize]
+            } else {
+                BARS[3]
+            }
+        })
+        .collect()
+}
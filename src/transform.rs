@@ -0,0 +1,228 @@
+// src/transform.rs
+//! Processing stage applied to every payload before it reaches the outputs: renaming and dropping
+//! fields, rewriting labels with regular expressions and converting units.
+
+use regex::Regex;
+use serde_json::{Map, Value, json};
+
+/// Where a transform applies: a metrics key (`cpu_freq_mhz`) or a field of its records
+/// (`disk_usage.used_percent`). `*` as the collector matches every collector.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub collector: String,
+    pub field: Option<String>,
+}
+
+impl Path {
+    fn parse(s: &str) -> Self {
+        match s.split_once('.') {
+            Some((collector, field)) => Path {
+                collector: collector.replace('-', "_"),
+                field: Some(field.to_string()),
+            },
+            None => Path {
+                collector: s.replace('-', "_"),
+                field: None,
+            },
+        }
+    }
+
+    fn matches(&self, collector: &str) -> bool {
+        self.collector == "*" || self.collector == collector
+    }
+}
+
+/// A single processing step.
+#[derive(Debug, Clone)]
+pub enum Transform {
+    /// `rename <path> <name>`
+    Rename { path: Path, to: String },
+    /// `drop <path>`
+    Drop { path: Path },
+    /// `relabel <source> <regex> <target> <replacement>`: when the source label (or `hostname`)
+    /// fully matches the regex, the target label is set to the expanded replacement.
+    Relabel {
+        source: String,
+        regex: Regex,
+        target: String,
+        replacement: String,
+    },
+    /// `convert <path> <from> <to>`, e.g. `convert smart_log.temperature kelvin celsius`.
+    Convert {
+        path: Path,
+        from: &'static str,
+        to: &'static str,
+    },
+}
+
+/// Units `convert` knows, as (name, dimension, factor, offset) with
+/// `base = value * factor + offset`.
+const UNITS: &[(&str, &str, f64, f64)] = &[
+    ("bytes", "bytes", 1.0, 0.0),
+    ("kb", "bytes", 1e3, 0.0),
+    ("mb", "bytes", 1e6, 0.0),
+    ("gb", "bytes", 1e9, 0.0),
+    ("tb", "bytes", 1e12, 0.0),
+    ("kib", "bytes", 1024.0, 0.0),
+    ("mib", "bytes", 1048576.0, 0.0),
+    ("gib", "bytes", 1073741824.0, 0.0),
+    ("tib", "bytes", 1099511627776.0, 0.0),
+    ("us", "seconds", 1e-6, 0.0),
+    ("ms", "seconds", 1e-3, 0.0),
+    ("s", "seconds", 1.0, 0.0),
+    ("min", "seconds", 60.0, 0.0),
+    ("h", "seconds", 3600.0, 0.0),
+    ("hz", "hertz", 1.0, 0.0),
+    ("khz", "hertz", 1e3, 0.0),
+    ("mhz", "hertz", 1e6, 0.0),
+    ("ghz", "hertz", 1e9, 0.0),
+    ("ratio", "ratio", 1.0, 0.0),
+    ("percent", "ratio", 0.01, 0.0),
+    ("kelvin", "temperature", 1.0, 0.0),
+    ("celsius", "temperature", 1.0, 273.15),
+    ("fahrenheit", "temperature", 5.0 / 9.0, 459.67 * 5.0 / 9.0),
+];
+
+/// Function to look up a unit by name.
+fn unit(name: &str) -> Result<(&'static str, &'static str, f64, f64), String> {
+    UNITS
+        .iter()
+        .find(|(unit, ..)| unit.eq_ignore_ascii_case(name))
+        .copied()
+        .ok_or_else(|| format!("unknown unit `{}`", name))
+}
+
+/// Function to convert a value between two units of the same dimension.
+pub fn convert_unit(value: f64, from: &str, to: &str) -> Result<f64, String> {
+    let (_, from_dim, from_factor, from_offset) = unit(from)?;
+    let (_, to_dim, to_factor, to_offset) = unit(to)?;
+    if from_dim != to_dim {
+        return Err(format!("can not convert {} to {}", from, to));
+    }
+    Ok((value * from_factor + from_offset - to_offset) / to_factor)
+}
+
+/// Function to parse a transform such as `rename disk_usage.used_percent pct_used`,
+/// `drop network.tx_bytes`, `relabel hostname (\w+)-\d+ role $1` or `convert uptime s h`.
+pub fn parse_transform(s: &str) -> Result<Transform, String> {
+    let tokens: Vec<&str> = s.split_whitespace().collect();
+    match tokens.as_slice() {
+        ["rename", path, to] => Ok(Transform::Rename {
+            path: Path::parse(path),
+            to: to.to_string(),
+        }),
+        ["drop", path] => Ok(Transform::Drop {
+            path: Path::parse(path),
+        }),
+        ["relabel", source, regex, target, replacement] => {
+            let regex = Regex::new(&format!("^(?:{})$", regex))
+                .map_err(|e| format!("invalid regex `{}`: {}", regex, e))?;
+            Ok(Transform::Relabel {
+                source: source.to_string(),
+                regex,
+                target: target.to_string(),
+                replacement: replacement.to_string(),
+            })
+        }
+        ["convert", path, from, to] => {
+            let (from, ..) = unit(from)?;
+            let (to, ..) = unit(to)?;
+            convert_unit(0.0, from, to)?;
+            Ok(Transform::Convert {
+                path: Path::parse(path),
+                from,
+                to,
+            })
+        }
+        _ => Err(format!(
+            "expected rename <path> <name>, drop <path>, relabel <source> <regex> <target> \
+             <replacement> or convert <path> <from> <to>, got `{}`",
+            s
+        )),
+    }
+}
+
+/// Function to run a field transform over every record of the matching collectors.
+/// A path without a field applies to the metrics key itself.
+fn for_each_field(
+    metrics: &mut Map<String, Value>,
+    path: &Path,
+    mut apply: impl FnMut(&mut Map<String, Value>, &str),
+) {
+    match &path.field {
+        None => apply(metrics, &path.collector),
+        Some(field) => {
+            for (_, value) in metrics.iter_mut().filter(|(key, _)| path.matches(key)) {
+                match value {
+                    Value::Array(records) => records
+                        .iter_mut()
+                        .filter_map(Value::as_object_mut)
+                        .for_each(|record| apply(record, field)),
+                    Value::Object(record) => apply(record, field),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Function to apply the transforms to a payload, in order.
+pub fn apply_transforms(transforms: &[Transform], payload: &mut Value) {
+    for transform in transforms {
+        if let Transform::Relabel {
+            source,
+            regex,
+            target,
+            replacement,
+        } = transform
+        {
+            let value = match source.as_str() {
+                "hostname" => payload["hostname"].as_str(),
+                label => payload["labels"][label].as_str(),
+            };
+            let Some(value) = value.filter(|v| regex.is_match(v)) else {
+                continue;
+            };
+            let rewritten = json!(regex.replace(value, replacement.as_str()));
+            if target == "hostname" {
+                payload["hostname"] = rewritten;
+            } else if let Value::Object(payload) = payload {
+                let labels = payload.entry("labels").or_insert_with(|| json!({}));
+                labels[target.as_str()] = rewritten;
+            }
+            continue;
+        }
+
+        let Some(metrics) = payload.get_mut("metrics").and_then(Value::as_object_mut) else {
+            return;
+        };
+        match transform {
+            Transform::Rename { path, to } => for_each_field(metrics, path, |record, field| {
+                if let Some(value) = record.remove(field) {
+                    record.insert(to.clone(), value);
+                }
+            }),
+            Transform::Drop { path } if path.field.is_none() => {
+                metrics.retain(|key, _| !path.matches(key));
+            }
+            Transform::Drop { path } => for_each_field(metrics, path, |record, field| {
+                record.remove(field);
+            }),
+            Transform::Convert { path, from, to } => {
+                for_each_field(metrics, path, |record, field| {
+                    let Some(value) = record.get_mut(field) else {
+                        return;
+                    };
+                    // Some collectors report numbers as strings (e.g. uptime).
+                    let number = value
+                        .as_f64()
+                        .or_else(|| value.as_str().and_then(|s| s.parse().ok()));
+                    if let Some(converted) = number.and_then(|n| convert_unit(n, from, to).ok()) {
+                        *value = json!(converted);
+                    }
+                })
+            }
+            Transform::Relabel { .. } => {}
+        }
+    }
+}
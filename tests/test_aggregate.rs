@@ -0,0 +1,74 @@
+use tinycollectd::aggregate::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn payload(timestamp: u64, used: f64) -> Value {
+        json!({
+            "timestamp": timestamp,
+            "hostname": "host",
+            "metrics": {
+                "disk_usage": [{"mount": "/", "used_percent": used}],
+                "uptime": {"uptime": timestamp.to_string()},
+            },
+            "errors": [],
+        })
+    }
+
+    #[test]
+    fn test_percentile() {
+        let sorted: Vec<f64> = (1..=20).map(f64::from).collect();
+        assert_eq!(percentile(&sorted, 95.0), 19.0);
+        assert_eq!(percentile(&sorted, 100.0), 20.0);
+        assert_eq!(percentile(&[7.0], 95.0), 7.0);
+    }
+
+    #[test]
+    fn test_aggregator_windows() {
+        let mut aggregator = Aggregator::new(60);
+        assert!(aggregator.push(payload(60, 10.0)).is_none());
+        assert!(aggregator.push(payload(70, 40.0)).is_none());
+        assert!(aggregator.push(payload(80, 20.0)).is_none());
+
+        let window = aggregator.push(payload(120, 99.0)).unwrap();
+        assert_eq!(window["timestamp"], 60);
+        assert_eq!(window["window"], 60);
+        let disk = &window["metrics"]["disk_usage"][0];
+        assert_eq!(disk["mount"], "/");
+        assert_eq!(disk["used_percent"], 20.0);
+        assert_eq!(disk["used_percent_min"], 10.0);
+        assert_eq!(disk["used_percent_max"], 40.0);
+        assert_eq!(disk["used_percent_avg"], 70.0 / 3.0);
+        assert_eq!(disk["used_percent_p95"], 40.0);
+        // Non-numeric fields keep their last value only.
+        assert_eq!(window["metrics"]["uptime"], json!({"uptime": "80"}));
+
+        let partial = aggregator.take().unwrap();
+        assert_eq!(partial["timestamp"], 120);
+        assert_eq!(
+            partial["metrics"]["disk_usage"][0]["used_percent_max"],
+            99.0
+        );
+        assert!(aggregator.take().is_none());
+    }
+
+    #[test]
+    fn test_aggregator_late_payload() {
+        let mut aggregator = Aggregator::new(60);
+        assert!(aggregator.push(payload(100, 10.0)).is_none());
+        let first = aggregator.push(payload(120, 30.0)).unwrap();
+        assert_eq!(first["timestamp"], 60);
+
+        // A slower group publishes its :119 sample after the :120 one.
+        assert!(aggregator.push(payload(119, 50.0)).is_none());
+        assert!(aggregator.push(payload(130, 20.0)).is_none());
+
+        let second = aggregator.push(payload(180, 0.0)).unwrap();
+        assert_eq!(second["timestamp"], 120);
+        let disk = &second["metrics"]["disk_usage"][0];
+        assert_eq!(disk["used_percent_max"], 50.0);
+        assert_eq!(disk["used_percent_min"], 20.0);
+        assert_eq!(aggregator.take().unwrap()["timestamp"], 180);
+    }
+}
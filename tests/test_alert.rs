@@ -0,0 +1,83 @@
+use tinycollectd::alert::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn payload(timestamp: u64, disks: Value) -> Value {
+        json!({
+            "timestamp": timestamp,
+            "hostname": "host",
+            "metrics": { "disk_usage": disks },
+            "errors": [],
+        })
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let rule = parse_rule("disk_usage.used_percent > 90 for 3 cycles").unwrap();
+        assert_eq!(rule.collector, "disk_usage");
+        assert_eq!(rule.field, "used_percent");
+        assert_eq!(rule.op, Op::Gt);
+        assert_eq!(rule.threshold, Threshold::Number(90.0));
+        assert_eq!(rule.cycles, 3);
+        assert_eq!(rule.to_string(), "disk_usage.used_percent > 90 for 3");
+
+        let rule = parse_rule("services.status != active").unwrap();
+        assert_eq!(rule.threshold, Threshold::Text("active".to_string()));
+        assert_eq!(rule.cycles, 1);
+
+        assert!(parse_rule("disk_usage.used_percent > full").is_err());
+        assert!(parse_rule("used_percent > 90").is_err());
+        assert!(parse_rule("disk_usage.used_percent ~ 90").is_err());
+        assert!(parse_rule("disk_usage.used_percent > 90 for 0").is_err());
+    }
+
+    #[test]
+    fn test_alert_fires_and_resolves_on_transitions() {
+        let rule = parse_rule("disk_usage.used_percent > 90 for 2").unwrap();
+        let mut engine = AlertEngine::new(vec![rule]);
+        let full = json!([{"mount": "/", "used_percent": 95.0}]);
+        let fine = json!([{"mount": "/", "used_percent": 50.0}]);
+
+        assert!(engine.evaluate(&payload(10, full.clone())).is_none());
+        let fired = engine.evaluate(&payload(20, full.clone())).unwrap();
+        let event = &fired["metrics"]["alerts"][0];
+        assert_eq!(event["state"], "firing");
+        assert_eq!(event["instance"], "/");
+        assert_eq!(event["value"], 95.0);
+        assert_eq!(fired["hostname"], "host");
+
+        // Still firing: nothing new to say.
+        assert!(engine.evaluate(&payload(30, full)).is_none());
+        // Other collectors' cycles leave the state alone.
+        assert!(
+            engine
+                .evaluate(&json!({"metrics": {"uptime": {}}}))
+                .is_none()
+        );
+
+        let resolved = engine.evaluate(&payload(40, fine)).unwrap();
+        let event = &resolved["metrics"]["alerts"][0];
+        assert_eq!(event["state"], "resolved");
+        assert_eq!(event["since"], 20);
+    }
+
+    #[test]
+    fn test_alert_resolves_when_instance_disappears() {
+        let rule = parse_rule("disk_usage.used_percent >= 90").unwrap();
+        let mut engine = AlertEngine::new(vec![rule.clone()]);
+        let fired = engine.evaluate(&payload(
+            10,
+            json!([{"mount": "/mnt", "used_percent": 99.0}]),
+        ));
+        assert!(fired.is_some());
+
+        // A reload that keeps the rule does not fire it again.
+        engine.set_rules(vec![rule]);
+        let resolved = engine.evaluate(&payload(20, json!([]))).unwrap();
+        let event = &resolved["metrics"]["alerts"][0];
+        assert_eq!(event["state"], "resolved");
+        assert_eq!(event["value"], Value::Null);
+    }
+}
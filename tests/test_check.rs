@@ -0,0 +1,74 @@
+use tinycollectd::check::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tinycollectd::collector::{CollectorError, ErrorKind, NvmesSmartLog};
+
+    #[test]
+    fn test_check_disk_thresholds() {
+        let disks = vec![
+            json!({"mount": "/", "used_percent": 42.04}),
+            json!({"mount": "/var", "used_percent": 85.0}),
+        ];
+
+        let result = check_disk(&disks, &[], 80.0, 90.0);
+        assert_eq!(result.status, Status::Warning);
+        assert_eq!(result.status.exit_code(), 1);
+        assert_eq!(
+            result.line(),
+            "DISK WARNING - /var 85.0% used | '/'=42%;80;90;0;100 '/var'=85%;80;90;0;100"
+        );
+
+        let result = check_disk(&disks, &["/".to_string()], 80.0, 90.0);
+        assert_eq!(result.status, Status::Ok);
+        assert_eq!(result.perfdata.len(), 1);
+
+        let result = check_disk(&disks, &["/srv".to_string()], 80.0, 90.0);
+        assert_eq!(result.status.exit_code(), 3);
+    }
+
+    #[test]
+    fn test_check_smart() {
+        let healthy = NvmesSmartLog {
+            nvme_name: "nvme0".to_string(),
+            critical_warning: Some(0),
+            temperature: Some(314),
+            percent_used: Some(3),
+            ..Default::default()
+        };
+        let result = check_smart(std::slice::from_ref(&healthy), &[]);
+        assert_eq!(result.status, Status::Ok);
+        assert_eq!(
+            result.line(),
+            "SMART OK - 1 NVMe controller(s) healthy | 'nvme0_temperature'=40.85 \
+             'nvme0_percent_used'=3%;;;0 'nvme0_media_errors'=0c"
+        );
+
+        let failing = NvmesSmartLog {
+            nvme_name: "nvme1".to_string(),
+            critical_warning: Some(4),
+            ..Default::default()
+        };
+        let error = CollectorError::new("smart_log", ErrorKind::PermissionDenied, "denied");
+        let result = check_smart(&[healthy, failing], &[error]);
+        assert_eq!(result.status.exit_code(), 2);
+        assert!(result.summary.contains("nvme1 critical warning 0x04"));
+    }
+
+    #[test]
+    fn test_check_service() {
+        let service = |status| json!({"service_name": "sshd", "status": status});
+        assert_eq!(check_service(&[service("active")]).status, Status::Ok);
+        assert_eq!(
+            check_service(&[service("reloading")]).status,
+            Status::Warning
+        );
+        assert_eq!(check_service(&[service("failed")]).status, Status::Critical);
+        assert_eq!(check_service(&[service("unknown")]).status, Status::Unknown);
+        assert_eq!(
+            check_service(&[service("active"), service("inactive")]).line(),
+            "SERVICE CRITICAL - sshd is active, sshd is inactive"
+        );
+    }
+}
@@ -32,6 +32,20 @@ mod tests {
         assert!(freq_str.parse::<u64>().is_ok());
     }
 
+    #[cfg(not(miri))]
+    #[test]
+    fn test_collect_cpufreq_keeps_legacy_field() {
+        let mut sys = create_test_system();
+        let (metrics, _) = collect_metric(
+            &MetricType::Cpufreq,
+            &mut sys,
+            &CollectorConfig::default(),
+            &mut CollectorState::default(),
+        );
+        assert_eq!(metrics["cpu_freq_mhz"], cpu_freq_json(&sys)["cpu_freq_mhz"]);
+        assert!(metrics["cpufreq"].is_array());
+    }
+
     #[cfg(not(miri))]
     #[test]
     fn test_get_if_data() {
@@ -78,6 +92,7 @@ mod tests {
         assert!(sysinfo["hostname"].is_string());
         assert!(sysinfo["uptime"].is_string());
         assert!(sysinfo["cpu_freq_mhz"].is_string());
+        assert!(sysinfo["cpufreq"].is_array());
         assert!(sysinfo["disk_usage"].is_array());
         assert!(sysinfo["network"].is_array());
         let timestamp = sysinfo["timestamp"].as_u64().unwrap();
@@ -110,4 +125,247 @@ mod tests {
             assert!(tx_bytes >= 0);
         }
     }
+
+    #[cfg(not(miri))]
+    #[test]
+    fn test_get_service_status_with_errors() {
+        let services = vec!["tinycollectd-test-nonexistent.service".to_string()];
+        let (statuses, errors) = get_service_status_with_errors(&services);
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0]["service_name"], services[0]);
+        let status = statuses[0]["status"].as_str().unwrap();
+        assert!(!status.is_empty());
+        if status == "unknown" {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].collector, "services");
+            assert_eq!(errors[0].instance.as_deref(), Some(services[0].as_str()));
+        }
+    }
+
+    #[test]
+    fn test_collector_error_json() {
+        let err = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        let entry =
+            serde_json::to_value(CollectorError::from_io("smart_log", "/dev/nvme0", &err)).unwrap();
+        assert_eq!(entry["collector"], "smart_log");
+        assert_eq!(entry["instance"], "/dev/nvme0");
+        assert_eq!(entry["kind"], "permission_denied");
+        assert!(entry["message"].is_string());
+
+        let entry =
+            serde_json::to_value(CollectorError::new("network", ErrorKind::Timeout, "slow"))
+                .unwrap();
+        assert!(entry["instance"].is_null());
+        assert_eq!(entry["kind"], "timeout");
+    }
+
+    #[cfg(not(miri))]
+    #[test]
+    fn test_get_agent_stats() {
+        use std::time::Duration;
+        use tinycollectd::stats::STATS;
+
+        STATS.record_sent(100, 1);
+        STATS.record_duration("network", Duration::from_millis(5));
+        let mut sys = create_test_system();
+        let stats = get_agent_stats(&mut sys);
+        assert!(stats.is_object());
+        assert_eq!(stats["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(stats["pid"], std::process::id());
+        assert!(stats["rss_bytes"].as_u64().unwrap() > 0);
+        assert!(stats["bytes_sent"].as_u64().unwrap() >= 100);
+        assert!(stats["datagrams_sent"].as_u64().unwrap() >= 1);
+        assert_eq!(stats["collection_duration_ms"]["network"], 5.0);
+    }
+
+    #[test]
+    fn test_filter() {
+        let patterns = |list: &[&str]| -> Vec<Pattern> {
+            list.iter().map(|p| parse_pattern(p).unwrap()).collect()
+        };
+
+        let filter = Filter::new(Vec::new(), patterns(&["lo", "veth*", "~^docker[0-9]+$"]));
+        assert!(filter.allows("eth0"));
+        assert!(!filter.allows("lo"));
+        assert!(!filter.allows("veth1a2b3c"));
+        assert!(!filter.allows("docker0"));
+        assert!(filter.allows("docker_gwbridge"));
+        // Globs match the whole name and treat regex characters literally.
+        assert!(filter.allows("lo0"));
+        assert!(Filter::new(patterns(&["/var/log.d"]), Vec::new()).allows("/var/log.d"));
+        assert!(!Filter::new(patterns(&["/var/log.d"]), Vec::new()).allows("/var/logxd"));
+
+        let filter = Filter::new(patterns(&["nvme?"]), patterns(&["nvme1"]));
+        assert!(filter.allows("nvme0"));
+        assert!(!filter.allows("nvme1"));
+        assert!(!filter.allows("nvme10"));
+
+        assert!(parse_pattern("~[").is_err());
+    }
+
+    #[cfg(not(miri))]
+    #[test]
+    fn test_get_if_data_filtered() {
+        let filter = Filter::new(Vec::new(), vec![parse_pattern("*").unwrap()]);
+        assert!(get_if_data_filtered(&filter).is_empty());
+        assert!(get_disk_usage_filtered(&Filter::default(), &filter).is_empty());
+    }
+
+    #[test]
+    fn test_cpu_usage() {
+        let before = parse_proc_stat(
+            "cpu  100 0 50 800 50 0 0 0 0 0\n\
+             cpu0 100 0 50 800 50 0 0 0 0 0\n\
+             ctxt 1000\nprocesses 10\nprocs_running 1\nprocs_blocked 0\n",
+        );
+        let after = parse_proc_stat(
+            "cpu  160 0 70 900 70 0 0 0 20 0\n\
+             cpu0 160 0 70 900 70 0 0 0 20 0\n\
+             ctxt 1500\nprocesses 14\nprocs_running 3\nprocs_blocked 1\n",
+        );
+        assert_eq!(after.cpus.len(), 2);
+        assert_eq!(after.cpus[0].cpu, "all");
+        assert_eq!(after.procs_running, 3);
+
+        let records = cpu_usage(Some((&before, 2.0)), &after);
+        assert_eq!(records.len(), 2);
+        let all = &records[0];
+        assert_eq!(all["cpu"], "all");
+        assert_eq!(all["user_percent"], 30.0);
+        assert_eq!(all["system_percent"], 10.0);
+        assert_eq!(all["idle_percent"], 50.0);
+        assert_eq!(all["iowait_percent"], 10.0);
+        // Guest time is part of user time and does not count towards the total.
+        assert_eq!(all["guest_percent"], 10.0);
+        assert_eq!(all["context_switches_per_sec"], 250.0);
+        assert_eq!(all["forks_per_sec"], 2.0);
+        assert_eq!(all["procs_blocked"], 1);
+        assert_eq!(records[1]["cpu"], "cpu0");
+        assert!(records[1].get("context_switches").is_none());
+
+        // The first sample only records a baseline: counters, but no percentages or rates.
+        let records = cpu_usage(None, &before);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["context_switches"], 1000);
+        assert!(records[0].get("idle_percent").is_none());
+        assert!(records[0].get("forks_per_sec").is_none());
+    }
+
+    #[cfg(not(miri))]
+    #[test]
+    fn test_read_cpufreq() {
+        let root =
+            std::env::temp_dir().join(format!("tinycollectd-cpufreq-{}", std::process::id()));
+        let write = |cpu: &str, file: &str, value: &str| {
+            let dir = root.join(cpu).join("cpufreq");
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(file), value).unwrap();
+        };
+        for cpu in ["cpu10", "cpu2"] {
+            write(cpu, "scaling_cur_freq", "1200000\n");
+            write(cpu, "scaling_min_freq", "800000\n");
+            write(cpu, "scaling_max_freq", "3500000\n");
+            write(cpu, "scaling_governor", "powersave\n");
+        }
+        write("cpu2", "energy_performance_preference", "balance_power\n");
+        std::fs::create_dir_all(root.join("cpufreq")).unwrap();
+
+        let (records, errors) = read_cpufreq(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["cpu"], "cpu2");
+        assert_eq!(records[0]["cur_freq_mhz"], 1200.0);
+        assert_eq!(records[0]["max_freq_mhz"], 3500.0);
+        assert_eq!(records[0]["governor"], "powersave");
+        assert_eq!(records[0]["energy_performance_preference"], "balance_power");
+        assert_eq!(records[1]["cpu"], "cpu10");
+        assert!(records[1].get("energy_performance_preference").is_none());
+    }
+
+    #[test]
+    fn test_parse_loadavg() {
+        let load = parse_loadavg("2.00 1.50 0.75 3/412 98765\n", 4).unwrap();
+        assert_eq!(load["load1"], 2.0);
+        assert_eq!(load["load15"], 0.75);
+        assert_eq!(load["load1_per_cpu"], 0.5);
+        assert_eq!(load["load5_per_cpu"], 0.375);
+        assert_eq!(load["runnable_tasks"], 3);
+        assert_eq!(load["total_tasks"], 412);
+        assert_eq!(load["last_pid"], 98765);
+        assert!(parse_loadavg("2.00 1.50", 4).is_err());
+        assert!(parse_loadavg("a 1.50 0.75 3/412 98765", 4).is_err());
+    }
+
+    #[test]
+    fn test_parse_meminfo() {
+        let memory = parse_meminfo(
+            "MemTotal:        8000000 kB\n\
+             MemFree:         1000000 kB\n\
+             MemAvailable:    6000000 kB\n\
+             Buffers:          200000 kB\n\
+             Cached:          4000000 kB\n\
+             SwapCached:         1000 kB\n\
+             SwapTotal:       2000000 kB\n\
+             SwapFree:        1500000 kB\n\
+             SReclaimable:     300000 kB\n\
+             HugePages_Total:      16\n\
+             HugePages_Free:        4\n\
+             Hugepagesize:       2048 kB\n",
+        )
+        .unwrap();
+        assert_eq!(memory["total_bytes"], 8_192_000_000u64);
+        assert_eq!(memory["used_bytes"], 2_048_000_000u64);
+        assert_eq!(memory["used_percent"], 25.0);
+        assert_eq!(memory["cached_bytes"], 4_403_200_000u64);
+        assert_eq!(memory["swap_used_bytes"], 512_000_000u64);
+        assert_eq!(memory["hugepages_total"], 16);
+        assert_eq!(memory["hugepages_free"], 4);
+        assert_eq!(memory["hugepage_size_bytes"], 2_097_152);
+        assert!(memory.get("dirty_bytes").is_none());
+        assert!(parse_meminfo("MemFree: 1 kB\n").is_err());
+    }
+
+    #[cfg(not(miri))]
+    #[test]
+    fn test_read_cgroup_pressure() {
+        let mounts = "proc /proc proc rw 0 0\ncgroup2 /sys/fs/cgroup cgroup2 rw 0 0\n";
+        assert_eq!(
+            cgroup2_mount(mounts),
+            Some(std::path::PathBuf::from("/sys/fs/cgroup"))
+        );
+        assert_eq!(cgroup2_mount("proc /proc proc rw 0 0\n"), None);
+
+        let root = std::env::temp_dir().join(format!("tinycollectd-psi-{}", std::process::id()));
+        let service = root.join("system.slice").join("db.service");
+        std::fs::create_dir_all(service.join("nested")).unwrap();
+        std::fs::write(
+            service.join("memory.pressure"),
+            "some avg10=1.50 avg60=0.75 avg300=0.25 total=12345\n\
+             full avg10=0.50 avg60=0.00 avg300=0.00 total=678\n",
+        )
+        .unwrap();
+        std::fs::write(
+            service.join("nested").join("cpu.pressure"),
+            "some avg10=0.00 avg60=0.00 avg300=0.00 total=1\n",
+        )
+        .unwrap();
+
+        let (records, errors) = read_cgroup_pressure(&root, &Filter::default(), 2);
+        assert!(errors.is_empty());
+        // Directories without pressure files and cgroups past the depth are left out.
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["cgroup"], "/system.slice/db.service");
+        assert_eq!(records[0]["memory_some_avg10"], 1.5);
+        assert_eq!(records[0]["memory_full_total_us"], 678);
+        assert!(records[0].get("cpu_some_avg10").is_none());
+
+        let include = Filter::new(vec![parse_pattern("*/nested").unwrap()], Vec::new());
+        assert!(read_cgroup_pressure(&root, &include, 2).0.is_empty());
+        let (records, _) = read_cgroup_pressure(&root, &include, 3);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["cgroup"], "/system.slice/db.service/nested");
+        assert_eq!(records[0]["cpu_some_total_us"], 1);
+    }
 }
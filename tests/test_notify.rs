@@ -0,0 +1,39 @@
+use tinycollectd::notify::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixDatagram;
+    use std::time::Duration;
+
+    #[cfg(not(miri))]
+    #[test]
+    fn test_notify_sends_to_socket() {
+        let path = std::env::temp_dir().join(format!("tinycollectd-notify-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let receiver = UnixDatagram::bind(&path).unwrap();
+        let pid = std::process::id().to_string();
+
+        let notifier = Notifier::new(path.to_str(), Some("30000000"), Some(&pid));
+        assert_eq!(notifier.watchdog(), Some(Duration::from_secs(30)));
+        notifier.notify("READY=1").unwrap();
+        notifier.status("ok");
+        notifier.ping_watchdog();
+
+        let mut buf = [0u8; 256];
+        let n = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"READY=1");
+        let n = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"STATUS=ok");
+        let n = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"WATCHDOG=1");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_watchdog_for_other_pid_is_ignored() {
+        let notifier = Notifier::new(None, Some("30000000"), Some("1"));
+        assert_eq!(notifier.watchdog(), None);
+        // Without NOTIFY_SOCKET nothing is sent and nothing fails.
+        assert!(notifier.notify("READY=1").is_ok());
+    }
+}
@@ -0,0 +1,397 @@
+use tinycollectd::output::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    /// Helper function to serve canned HTTP responses and hand back each raw request.
+    async fn spawn_server(
+        status: u16,
+        body: &'static str,
+    ) -> (String, mpsc::UnboundedReceiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/ingest", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut raw = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    raw.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&raw);
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let len = text[..end]
+                            .lines()
+                            .find_map(|l| {
+                                l.to_lowercase()
+                                    .strip_prefix("content-length:")
+                                    .map(|v| v.trim().parse::<usize>().unwrap())
+                            })
+                            .unwrap_or(0);
+                        if raw.len() >= end + 4 + len || n == 0 {
+                            break;
+                        }
+                    }
+                }
+                let _ = tx.send(String::from_utf8_lossy(&raw).into_owned());
+                let resp = format!(
+                    "HTTP/1.1 {} X\r\ncontent-length: {}\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(resp.as_bytes()).await;
+            }
+        });
+
+        (url, rx)
+    }
+
+    /// Helper function to build a config for a test server.
+    fn test_config(url: String) -> HttpConfig {
+        HttpConfig {
+            url,
+            headers: vec![("Authorization".to_string(), "Bearer t0ken".to_string())],
+            batch_size: 2,
+            timeout: Duration::from_secs(2),
+            retries: 0,
+            spool: None,
+            spool_max_bytes: 1024 * 1024,
+        }
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("Authorization: Bearer a:b").unwrap(),
+            ("Authorization".to_string(), "Bearer a:b".to_string())
+        );
+        assert!(parse_header("no-colon").is_err());
+        assert!(parse_header(": value").is_err());
+    }
+
+    #[cfg(not(miri))]
+    #[tokio::test]
+    async fn test_http_sink_batches() {
+        let (url, mut requests) = spawn_server(200, "").await;
+        let mut sink = HttpSink::new(test_config(url)).unwrap();
+
+        sink.push(json!({"cycle": 1})).await;
+        assert!(
+            requests.try_recv().is_err(),
+            "first payload should be held back"
+        );
+        sink.push(json!({"cycle": 2})).await;
+
+        let request = requests.recv().await.unwrap();
+        assert!(request.starts_with("POST /ingest"));
+        assert!(
+            request
+                .to_lowercase()
+                .contains("authorization: bearer t0ken")
+        );
+        let body: Value = serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body, json!([{"cycle": 1}, {"cycle": 2}]));
+    }
+
+    #[cfg(not(miri))]
+    #[tokio::test]
+    async fn test_http_sink_spools_on_5xx() {
+        let (url, _requests) = spawn_server(503, "").await;
+        let spool = std::env::temp_dir().join(format!("tinycollectd-spool-{}", std::process::id()));
+        let _ = std::fs::remove_file(&spool);
+
+        let mut config = test_config(url);
+        config.batch_size = 1;
+        config.spool = Some(spool.clone());
+        let mut sink = HttpSink::new(config).unwrap();
+        sink.push(json!({"cycle": 1})).await;
+
+        let spooled = std::fs::read_to_string(&spool).unwrap();
+        assert_eq!(spooled, "[{\"cycle\":1}]\n");
+        std::fs::remove_file(&spool).unwrap();
+    }
+
+    #[cfg(not(miri))]
+    #[tokio::test]
+    async fn test_http_sink_spool_handling() {
+        // 429 is retried like a 5xx and ends up in the spool instead of being dropped.
+        let (url, _requests) = spawn_server(429, "").await;
+        let spool =
+            std::env::temp_dir().join(format!("tinycollectd-spool-429-{}", std::process::id()));
+        let _ = std::fs::remove_file(&spool);
+        let mut config = test_config(url);
+        config.batch_size = 1;
+        config.spool = Some(spool.clone());
+        HttpSink::new(config.clone())
+            .unwrap()
+            .push(json!({"cycle": 1}))
+            .await;
+        assert_eq!(std::fs::read_to_string(&spool).unwrap().lines().count(), 1);
+
+        // Once the endpoint is back, a bounded number of spooled batches is replayed per flush,
+        // oldest first, and the new batch queues behind what is left.
+        let backlog: String = (0..20)
+            .map(|i| format!("[{{\"cycle\":{}}}]\n", i))
+            .collect();
+        std::fs::write(&spool, backlog).unwrap();
+        let (url, mut requests) = spawn_server(200, "").await;
+        config.url = url;
+        let mut sink = HttpSink::new(config).unwrap();
+        sink.push(json!({"cycle": 20})).await;
+        let first = requests.recv().await.unwrap();
+        assert!(first.ends_with("[{\"cycle\":0}]"));
+
+        let offset_file = format!("{}.offset", spool.display());
+        let offset: usize = std::fs::read_to_string(&offset_file)
+            .unwrap()
+            .parse()
+            .unwrap();
+        let spooled = std::fs::read_to_string(&spool).unwrap();
+        let rest = &spooled[offset..];
+        assert_eq!(rest.lines().count(), 5);
+        assert!(rest.starts_with("[{\"cycle\":16}]"));
+        assert!(rest.ends_with("[{\"cycle\":20}]\n"));
+
+        // Draining the rest removes the spool, and new batches are sent directly again.
+        sink.push(json!({"cycle": 21})).await;
+        assert!(!spool.exists());
+        assert!(!std::path::Path::new(&offset_file).exists());
+        let last = std::iter::from_fn(|| requests.try_recv().ok())
+            .last()
+            .unwrap();
+        assert!(last.ends_with("[{\"cycle\":21}]"));
+    }
+
+    #[test]
+    fn test_rfc3339_and_index_name() {
+        assert_eq!(rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339(1_709_210_096), "2024-02-29T12:34:56Z");
+        assert_eq!(
+            index_name("tinycollectd", 1_709_210_096),
+            "tinycollectd-2024.02.29"
+        );
+    }
+
+    #[test]
+    fn test_flatten_payload() {
+        let payload = json!({
+            "timestamp": 1_709_210_096u64,
+            "hostname": "host1",
+            "metrics": {
+                "disk_usage": [{"mount": "/"}, {"mount": "/home"}],
+                "uptime": {"uptime": "42"},
+                "cpu_freq_mhz": "2400",
+            }
+        });
+        let docs = flatten_payload(&payload);
+        assert_eq!(docs.len(), 4);
+        for doc in &docs {
+            assert_eq!(doc["hostname"], "host1");
+            assert_eq!(doc["@timestamp"], "2024-02-29T12:34:56Z");
+        }
+        let mounts: Vec<_> = docs
+            .iter()
+            .filter(|d| d["collector"] == "disk_usage")
+            .map(|d| d["mount"].as_str().unwrap())
+            .collect();
+        assert_eq!(mounts, ["/", "/home"]);
+        assert!(
+            docs.iter()
+                .any(|d| d["collector"] == "sys" && d["cpu_freq_mhz"] == "2400")
+        );
+    }
+
+    #[cfg(not(miri))]
+    #[tokio::test]
+    async fn test_elastic_sink_retries_failed_items() {
+        let (url, mut requests) = spawn_server(
+            200,
+            r#"{"errors":true,"items":[{"index":{"status":201}},{"index":{"status":503}},{"index":{"status":400,"error":"mapping"}}]}"#,
+        )
+        .await;
+        let sink = ElasticSink::new(ElasticConfig {
+            url,
+            index_prefix: "metrics".to_string(),
+            username: None,
+            password: None,
+            timeout: Duration::from_secs(2),
+            retries: 1,
+        })
+        .unwrap();
+
+        let payload = json!({
+            "timestamp": 0,
+            "hostname": "host1",
+            "metrics": {"network": [{"interface": "eth0"}, {"interface": "eth1"}, {"interface": "eth2"}]}
+        });
+        sink.send(&payload).await;
+
+        let first = requests.recv().await.unwrap();
+        assert!(first.starts_with("POST /ingest/_bulk"));
+        assert!(first.contains(r#"{"index":{"_index":"metrics-1970.01.01"}}"#));
+        assert_eq!(first.matches("\"interface\"").count(), 3);
+
+        let second = requests.recv().await.unwrap();
+        assert!(second.contains("eth1"));
+        assert!(!second.contains("eth0") && !second.contains("eth2"));
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[cfg(not(miri))]
+    #[tokio::test]
+    async fn test_elastic_sink_retries_truncated_response() {
+        let (url, mut requests) = spawn_server(
+            200,
+            r#"{"errors":false,"items":[{"index":{"status":201}}]}"#,
+        )
+        .await;
+        let sink = ElasticSink::new(ElasticConfig {
+            url,
+            index_prefix: "metrics".to_string(),
+            username: None,
+            password: None,
+            timeout: Duration::from_secs(2),
+            retries: 1,
+        })
+        .unwrap();
+
+        let payload = json!({
+            "timestamp": 0,
+            "hostname": "host1",
+            "metrics": {"network": [{"interface": "eth0"}, {"interface": "eth1"}]}
+        });
+        sink.send(&payload).await;
+
+        // One item for two documents: the whole request is retried, not just the first document.
+        for _ in 0..2 {
+            let request = requests.recv().await.unwrap();
+            assert!(request.contains("eth0") && request.contains("eth1"));
+        }
+        assert!(requests.try_recv().is_err());
+    }
+
+    /// Helper function to build a payload with one full disk and one failing drive.
+    fn problem_payload() -> Value {
+        json!({
+            "timestamp": 0,
+            "hostname": "host1",
+            "metrics": {
+                "disk_usage": [
+                    {"mount": "/", "used_percent": 95.5},
+                    {"mount": "/home", "used_percent": 10.0}
+                ],
+                "smart_log": [{"nvme_name": "nvme0", "critical_warning": 4}]
+            }
+        })
+    }
+
+    #[test]
+    fn test_log_records_severity() {
+        let filter = LogFilter {
+            disk_warn_percent: 90.0,
+            problems_only: true,
+        };
+        let records = log_records(&problem_payload(), &filter);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].severity, Severity::Warning);
+        assert_eq!(records[0].fields["mount"], "/");
+        assert_eq!(records[1].severity, Severity::Crit);
+        assert_eq!(records[1].collector, "smart_log");
+    }
+
+    #[test]
+    fn test_format_rfc5424() {
+        let filter = LogFilter {
+            disk_warn_percent: 90.0,
+            problems_only: true,
+        };
+        let record = &log_records(&problem_payload(), &filter)[0];
+        // 32473 is the enterprise number reserved for documentation (RFC 5612).
+        let line = format_rfc5424(record, Some(32473));
+        assert!(line.starts_with("<28>1 1970-01-01T00:00:00Z host1 tinycollectd "));
+        assert!(
+            line.contains(" disk_usage [tinycollectd@32473 mount=\"/\" used_percent=\"95.5\"] ")
+        );
+        assert!(line.ends_with("disk_usage mount=/ used_percent=95.5"));
+        assert!(format_rfc5424(record, None).contains(" disk_usage - disk_usage mount=/ "));
+    }
+
+    #[test]
+    fn test_parse_syslog_address() {
+        assert_eq!(
+            parse_syslog_address("udp://10.0.0.1:514").unwrap(),
+            SyslogTransport::Udp("10.0.0.1:514".to_string())
+        );
+        assert_eq!(
+            parse_syslog_address("unix:///dev/log").unwrap(),
+            SyslogTransport::Unix("/dev/log".into())
+        );
+        assert!(parse_syslog_address("10.0.0.1:514").is_err());
+    }
+
+    #[test]
+    fn test_encode_journal_entry() {
+        let filter = LogFilter {
+            disk_warn_percent: 90.0,
+            problems_only: false,
+        };
+        let record = &log_records(&problem_payload(), &filter)[2];
+        let entry = String::from_utf8(encode_journal_entry(record)).unwrap();
+        assert!(entry.contains("PRIORITY=2\n"));
+        assert!(entry.contains("HOSTNAME=host1\n"));
+        assert!(entry.contains("COLLECTOR=smart_log\n"));
+        assert!(entry.contains("NVME_NAME=nvme0\n"));
+        assert!(entry.contains("CRITICAL_WARNING=4\n"));
+        assert_eq!(journal_field_name("9-odd key"), "ODD_KEY");
+    }
+
+    #[test]
+    fn test_labels_reach_every_output() {
+        assert_eq!(
+            parse_label("dc=ams1").unwrap(),
+            ("dc".to_string(), "ams1".to_string())
+        );
+        assert!(parse_label("dc").is_err());
+        assert!(parse_label("data-center=ams1").is_err());
+
+        let mut payload = problem_payload();
+        apply_labels(&mut payload, &[("dc".to_string(), "ams1".to_string())]);
+        assert_eq!(payload["labels"]["dc"], "ams1");
+        assert!(
+            flatten_payload(&payload)
+                .iter()
+                .all(|doc| doc["labels"]["dc"] == "ams1")
+        );
+
+        let filter = LogFilter {
+            disk_warn_percent: 90.0,
+            problems_only: true,
+        };
+        let record = &log_records(&payload, &filter)[0];
+        assert!(!record.fields.contains_key("labels"));
+        assert!(
+            format_rfc5424(record, Some(32473)).contains("\"95.5\"][labels@32473 dc=\"ams1\"] ")
+        );
+        assert!(format_rfc5424(record, None).ends_with("used_percent=95.5 label.dc=ams1"));
+        let entry = String::from_utf8(encode_journal_entry(record)).unwrap();
+        assert!(entry.contains("LABEL_DC=ams1\n"));
+
+        let long = "x".repeat(70);
+        apply_labels(&mut payload, &[(long.clone(), "v".to_string())]);
+        let record = &log_records(&payload, &filter)[0];
+        let entry = String::from_utf8(encode_journal_entry(record)).unwrap();
+        let name = format!("LABEL_{}", "X".repeat(58));
+        assert!(entry.contains(&format!("\n{}=v\n", name)));
+        assert_eq!(name.len(), 64);
+    }
+}
@@ -0,0 +1,75 @@
+use tinycollectd::rate::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn payload(timestamp: u64, interfaces: Value) -> Value {
+        json!({"timestamp": timestamp, "metrics": {"network": interfaces}})
+    }
+
+    #[test]
+    fn test_counter_delta() {
+        assert_eq!(counter_delta(100, 150), Some(50));
+        // 32-bit counter wrapped.
+        assert_eq!(counter_delta(u32::MAX as u64 - 9, 5), Some(15));
+        // 64-bit counter wrapped.
+        assert_eq!(counter_delta(u64::MAX - 4, 5), Some(10));
+        // Reset, e.g. an interface was recreated.
+        assert_eq!(counter_delta(1_000_000, 10), None);
+    }
+
+    #[test]
+    fn test_rates_derive() {
+        let mut rates = Rates::default();
+        let mut first = payload(
+            10,
+            json!([{"interface": "eth0", "rx_bytes": 1000, "tx_bytes": 0}]),
+        );
+        rates.derive(&mut first);
+        assert!(
+            first["metrics"]["network"][0]
+                .get("rx_bytes_per_sec")
+                .is_none()
+        );
+
+        let mut second = payload(
+            20,
+            json!([{"interface": "eth0", "rx_bytes": 6000, "tx_bytes": 100}]),
+        );
+        rates.derive(&mut second);
+        assert_eq!(second["metrics"]["network"][0]["rx_bytes_per_sec"], 500.0);
+        assert_eq!(second["metrics"]["network"][0]["tx_bytes_per_sec"], 10.0);
+
+        // eth0 disappears and comes back with fresh counters: no bogus rate.
+        rates.derive(&mut payload(30, json!([])));
+        let mut back = payload(
+            40,
+            json!([{"interface": "eth0", "rx_bytes": 10, "tx_bytes": 0}]),
+        );
+        rates.derive(&mut back);
+        assert!(
+            back["metrics"]["network"][0]
+                .get("rx_bytes_per_sec")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_rates_smart_log() {
+        let mut rates = Rates::default();
+        let log = |units: u64, writes: u64| {
+            json!({"timestamp": 0, "metrics": {"smart_log": [
+                {"nvme_name": "nvme0", "data_units_written": units, "host_write_commands": writes}
+            ]}})
+        };
+        rates.derive(&mut log(0, 0));
+        let mut later = log(10, 500);
+        later["timestamp"] = json!(10);
+        rates.derive(&mut later);
+        let record = &later["metrics"]["smart_log"][0];
+        assert_eq!(record["written_bytes_per_sec"], 512_000.0);
+        assert_eq!(record["written_bytes_per_day"], 512_000.0 * 86_400.0);
+        assert_eq!(record["write_iops"], 50.0);
+    }
+}
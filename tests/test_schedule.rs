@@ -0,0 +1,174 @@
+use tinycollectd::collector::MetricType;
+use tinycollectd::schedule::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(
+            parse_interval("smart-log=300").unwrap(),
+            (MetricType::SmartLog, 300)
+        );
+        assert!(parse_interval("smart-log").is_err());
+        assert!(parse_interval("bogus=1").is_err());
+        assert!(parse_interval("network=soon").is_err());
+    }
+
+    #[test]
+    fn test_plan_groups_by_interval() {
+        let groups = plan(
+            &[MetricType::All],
+            10,
+            &[(MetricType::SmartLog, 300), (MetricType::Network, 1)],
+        )
+        .unwrap();
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].interval, Duration::from_secs(1));
+        assert_eq!(groups[0].metrics, [MetricType::Network]);
+        assert_eq!(groups[1].interval, Duration::from_secs(10));
+        assert_eq!(
+            groups[1].metrics,
+            [
+                MetricType::DiskUsage,
+                MetricType::Cpufreq,
+                MetricType::Uptime
+            ]
+        );
+        assert_eq!(groups[2].interval, Duration::from_secs(300));
+        assert_eq!(groups[2].metrics, [MetricType::SmartLog]);
+    }
+
+    #[test]
+    fn test_plan_keeps_all_without_overrides() {
+        let groups = plan(&[MetricType::All], 10, &[]).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].metrics, [MetricType::All]);
+    }
+
+    #[test]
+    fn test_plan_rejects_uncollected_override() {
+        assert!(plan(&[MetricType::Uptime], 10, &[(MetricType::Network, 1)]).is_err());
+        assert!(plan(&[MetricType::Uptime], 0, &[]).is_err());
+    }
+
+    #[test]
+    fn test_delay_to_boundary() {
+        let ten = Duration::from_secs(10);
+        assert_eq!(
+            delay_to_boundary(Duration::from_secs(1_000_003), ten, Duration::ZERO),
+            Duration::from_secs(7)
+        );
+        assert_eq!(
+            delay_to_boundary(Duration::from_secs(1_000_000), ten, Duration::ZERO),
+            Duration::ZERO
+        );
+        // A splay shifts every boundary, including past the interval length.
+        assert_eq!(
+            delay_to_boundary(Duration::from_secs(1_000_003), ten, Duration::from_secs(4)),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            delay_to_boundary(Duration::from_secs(1_000_003), ten, Duration::from_secs(14)),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[cfg(not(miri))]
+    #[test]
+    fn test_random_splay_bounds() {
+        assert_eq!(random_splay(Duration::ZERO), Duration::ZERO);
+        let splay = random_splay(Duration::from_secs(5));
+        assert!(splay < Duration::from_secs(5));
+        assert_eq!(random_splay(Duration::from_secs(5)), splay);
+    }
+
+    #[test]
+    fn test_timeout_for() {
+        let timing = Timing {
+            missed_tick: MissedTick::Skip,
+            splay: Duration::ZERO,
+            timeouts: vec![(MetricType::SmartLog, Duration::from_secs(2))],
+            cycles: None,
+        };
+        let interval = Duration::from_secs(10);
+        assert_eq!(
+            timing.timeout_for(&MetricType::SmartLog, interval),
+            Duration::from_secs(2)
+        );
+        assert_eq!(timing.timeout_for(&MetricType::Network, interval), interval);
+    }
+
+    #[cfg(not(miri))]
+    #[tokio::test]
+    async fn test_run_group_stops_after_cycles() {
+        let group = Group {
+            interval: Duration::from_millis(100),
+            metrics: vec![MetricType::Uptime],
+        };
+        let timing = Timing {
+            missed_tick: MissedTick::Skip,
+            splay: Duration::ZERO,
+            timeouts: Vec::new(),
+            cycles: Some(2),
+        };
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let (_stop, stopped) = tokio::sync::watch::channel(false);
+        let stalled = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(run_group(
+            group,
+            timing,
+            Default::default(),
+            tx,
+            stopped,
+            stalled.clone(),
+        ));
+
+        let mut payloads = Vec::new();
+        while let Some(payload) = rx.recv().await {
+            payloads.push(payload);
+        }
+        assert_eq!(payloads.len(), 2);
+        assert!(!payloads[0]["metrics"]["uptime"].is_null());
+        assert_eq!(stalled.load(Ordering::SeqCst), 0);
+    }
+
+    #[cfg(not(miri))]
+    #[tokio::test]
+    async fn test_run_group_counts_overrunning_collectors() {
+        let group = Group {
+            interval: Duration::from_millis(200),
+            metrics: vec![MetricType::All],
+        };
+        // Refreshing every process takes far longer than no time at all.
+        let timing = Timing {
+            missed_tick: MissedTick::Skip,
+            splay: Duration::ZERO,
+            timeouts: vec![(MetricType::All, Duration::ZERO)],
+            cycles: Some(2),
+        };
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let (_stop, stopped) = tokio::sync::watch::channel(false);
+        let stalled = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(run_group(
+            group,
+            timing,
+            Default::default(),
+            tx,
+            stopped,
+            stalled.clone(),
+        ));
+
+        let payload = rx.recv().await.unwrap();
+        assert_eq!(payload["errors"][0]["kind"], "timeout");
+        assert_eq!(stalled.load(Ordering::SeqCst), 1);
+
+        // The group no longer counts once it has exited.
+        while rx.recv().await.is_some() {}
+        assert_eq!(stalled.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,89 @@
+use tinycollectd::store::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn payload(timestamp: u64) -> Value {
+        json!({
+            "timestamp": timestamp,
+            "hostname": "host",
+            "metrics": {
+                "disk_usage": [
+                    {"mount": "/", "used_percent": timestamp as f64},
+                    {"mount": "/home", "used_percent": 1.0}
+                ],
+                "uptime": {"uptime": "42"}
+            },
+            "errors": []
+        })
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s").unwrap(), 90);
+        assert_eq!(parse_duration("2h").unwrap(), 7200);
+        assert_eq!(parse_duration("7d").unwrap(), 604_800);
+        assert_eq!(parse_duration("15").unwrap(), 15);
+        assert!(parse_duration("2w").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("999999999999999999d").is_err());
+    }
+
+    #[test]
+    fn test_select_and_sparkline() {
+        let payloads = vec![payload(1), payload(2)];
+        let samples = select(&payloads, "disk.used_percent");
+        assert_eq!(samples.len(), 4);
+        assert_eq!(
+            samples[2],
+            Sample {
+                timestamp: 2,
+                instance: "/".to_string(),
+                value: 2.0
+            }
+        );
+        assert_eq!(select(&payloads, "uptime.uptime")[0].value, 42.0);
+        assert!(select(&payloads, "network.rx_bytes").is_empty());
+
+        assert_eq!(sparkline(&[0.0, 1.0, 2.0, 7.0], 10), "▁▂▃█");
+        assert_eq!(sparkline(&[5.0, 5.0], 10), "▄▄");
+        assert_eq!(sparkline(&[0.0, 0.0, 7.0, 7.0], 2).chars().count(), 2);
+    }
+
+    #[cfg(not(miri))]
+    #[test]
+    fn test_store_rotates_and_prunes() {
+        let dir = std::env::temp_dir().join(format!("tinycollectd-store-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = Store::open(&dir, 8 * 1024).unwrap();
+
+        for timestamp in 0..2000 {
+            store.append(&payload(timestamp)).unwrap();
+        }
+        let size: u64 = std::fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|e| e.metadata().unwrap().len())
+            .sum();
+        assert!(size <= 8 * 1024);
+
+        let recent = store.read_since(1990).unwrap();
+        let timestamps: Vec<u64> = recent
+            .iter()
+            .map(|p| p["timestamp"].as_u64().unwrap())
+            .collect();
+        assert_eq!(timestamps, (1990..2000).collect::<Vec<_>>());
+        let all = store.read_since(0).unwrap();
+        assert_eq!(all.last().unwrap()["timestamp"], 1999);
+        // The oldest history was dropped to stay within the limit.
+        assert!(all[0]["timestamp"].as_u64().unwrap() > 0);
+
+        let reader = Store::open_existing(&dir).unwrap();
+        assert_eq!(reader.read_since(1990).unwrap(), recent);
+        assert!(Store::open_existing(&dir.join("missing")).is_err());
+        assert!(!dir.join("missing").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,82 @@
+use tinycollectd::transform::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn payload() -> Value {
+        json!({
+            "timestamp": 0,
+            "hostname": "web-042",
+            "labels": {"dc": "ams1"},
+            "metrics": {
+                "disk_usage": [
+                    {"mount": "/", "used_percent": 50.0, "total_gb": 100},
+                    {"mount": "/home", "used_percent": 10.0, "total_gb": 200}
+                ],
+                "uptime": {"uptime": "7200"},
+                "cpu_freq_mhz": 2400,
+                "network": []
+            }
+        })
+    }
+
+    fn apply(rules: &[&str]) -> Value {
+        let transforms: Vec<Transform> =
+            rules.iter().map(|r| parse_transform(r).unwrap()).collect();
+        let mut payload = payload();
+        apply_transforms(&transforms, &mut payload);
+        payload
+    }
+
+    #[test]
+    fn test_rename_and_drop() {
+        let payload = apply(&[
+            "rename disk_usage.used_percent pct_used",
+            "drop *.total_gb",
+            "drop network",
+            "rename cpu_freq_mhz cpu_mhz",
+        ]);
+        let disks = &payload["metrics"]["disk_usage"];
+        assert_eq!(disks[0], json!({"mount": "/", "pct_used": 50.0}));
+        assert_eq!(disks[1], json!({"mount": "/home", "pct_used": 10.0}));
+        assert!(payload["metrics"].get("network").is_none());
+        assert_eq!(payload["metrics"]["cpu_mhz"], 2400);
+    }
+
+    #[test]
+    fn test_relabel() {
+        let payload = apply(&[
+            r"relabel hostname (\w+)-\d+ role $1",
+            "relabel dc ams(\\d) dc amsterdam-$1",
+            "relabel dc nomatch dc never",
+        ]);
+        assert_eq!(
+            payload["labels"],
+            json!({"dc": "amsterdam-1", "role": "web"})
+        );
+        // Regexes must match the whole value.
+        let payload = apply(&["relabel hostname web role matched"]);
+        assert!(payload["labels"].get("role").is_none());
+    }
+
+    #[test]
+    fn test_convert() {
+        let payload = apply(&[
+            "convert uptime.uptime s h",
+            "convert disk_usage.used_percent percent ratio",
+            "convert cpu_freq_mhz mhz ghz",
+        ]);
+        assert_eq!(payload["metrics"]["uptime"]["uptime"], 2.0);
+        assert_eq!(payload["metrics"]["disk_usage"][0]["used_percent"], 0.5);
+        assert_eq!(payload["metrics"]["cpu_freq_mhz"], 2.4);
+
+        assert!((convert_unit(313.15, "kelvin", "celsius").unwrap() - 40.0).abs() < 1e-9);
+        assert!((convert_unit(212.0, "fahrenheit", "celsius").unwrap() - 100.0).abs() < 1e-9);
+        assert_eq!(convert_unit(1.0, "gib", "mib").unwrap(), 1024.0);
+        assert!(parse_transform("convert uptime s gb").is_err());
+        assert!(parse_transform("convert uptime s parsecs").is_err());
+        assert!(parse_transform("relabel hostname ( role x").is_err());
+        assert!(parse_transform("rename disk_usage").is_err());
+    }
+}